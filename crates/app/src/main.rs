@@ -1,13 +1,21 @@
+use async_trait::async_trait;
 use chrono::Utc;
-use clap::{Parser, Subcommand};
-use pdf_search_core::extract_page_texts;
+use clap::{Args, Parser, Subcommand};
+use pdf_search_core::{extract_page_texts, resolve_source_path};
 use pdf_search_core::{
-    ingest_folder_chunks_best_effort, CharacterNgramEmbedder, IngestionOptions, Neo4jStore,
-    OpenSearchStore, QdrantStore, SearchCoordinator, SearchError, SearchQuery, VectorIndex,
+    ingest_discovered, ingest_folder_chunks_best_effort, BackendTimeouts, CharacterNgramEmbedder,
+    ContextChunk, DiscoveryPolicy, DocumentFormat, Explanation, ExtractorChain, FolderWatcher,
+    Fusion, Highlight, IndexedFiles, IndexingStatus, IngestionOptions, LayoutExtractor,
+    LlmOcrExtractor, LopdfExtractor, ModeWeight, Neo4jStore, OcrCache, OcrExtractor,
+    OpenSearchStore, PageAcceptance, PasswordMap, PdfChunk, QdrantStore, Rerank,
+    ScoreNormalization, SearchCoordinator, SearchError, SearchQuery, SourceRegistry,
+    TesseractConfig, VectorIndex, WatchTarget, DEFAULT_RERANK_TOP_N, SNIPPET_CHARS,
 };
 use pdf_search_core::{Embedder, GraphIndex, KeywordIndex};
 use std::collections::HashSet;
+use std::io::IsTerminal;
 use std::path::Path;
+use std::time::Duration;
 use tracing::{info, warn};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
@@ -48,24 +56,76 @@ struct Cli {
     /// Neo4j password
     #[arg(long, default_value = "password")]
     neo4j_password: String,
+
+    /// JSON keyring of `{"pattern", "password"}` entries for encrypted PDFs
+    #[arg(long, env = "DOCNEEDLE_PASSWORD_FILE")]
+    password_file: Option<String>,
+
+    /// OCR pages without a text layer locally with pdftoppm + tesseract
+    #[arg(long, default_value_t = false)]
+    local_ocr: bool,
+
+    /// Tesseract language packs for local OCR, comma separated
+    #[arg(long, value_delimiter = ',', default_value = "eng")]
+    ocr_languages: Vec<String>,
+
+    /// Rasterisation resolution for local OCR
+    #[arg(long, default_value = "300")]
+    ocr_dpi: u32,
+
+    /// Read text layers with span positions and column-aware reading order
+    #[arg(long, default_value_t = false)]
+    layout: bool,
+
+    /// Minimum alphanumeric characters for a text-layer page to skip OCR
+    #[arg(long, default_value = "16")]
+    min_page_chars: usize,
+
+    /// Directory of cached OCR results
+    #[arg(long, default_value = ".docneedle/ocr-cache")]
+    ocr_cache_dir: String,
+
+    /// Always call the OCR backend instead of reusing cached results
+    #[arg(long, default_value_t = false)]
+    no_ocr_cache: bool,
+
+    /// Document formats to ingest, comma separated (pdf, html, markdown, text)
+    #[arg(long, value_delimiter = ',', default_value = "pdf")]
+    formats: Vec<DocumentFormat>,
+
+    /// Ingest documents inside ZIP/TAR archives and .eml attachments
+    #[arg(long, default_value_t = false)]
+    expand_archives: bool,
 }
 
-#[derive(Subcommand)]
+#[derive(Clone, Subcommand)]
 enum Command {
-    /// Ingest PDF folder and index chunks into all stores.
+    /// Ingest a document folder and index chunks into all stores.
     Ingest {
-        /// Folder that contains PDFs recursively.
+        /// Folder that contains documents recursively.
         #[arg(long)]
         folder: String,
+        /// Directory where the JSON ingestion report is written.
+        #[arg(long, default_value = ".docneedle/reports")]
+        report_dir: String,
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
     },
     /// Search all layers and return fused evidence with citations.
     Search {
-        /// Search query
+        /// Search query: words, "exact phrases", AND/OR/NOT, parentheses, -excluded and
+        /// standard:/version:/section:/clause: filters (`clause:5.2.*` for sub-clauses).
         #[arg(long)]
         query: String,
         /// Number of candidates to return.
         #[arg(long, default_value = "10")]
         top_k: usize,
+        /// Skip this many results, e.g. 10 for the second page of 10.
+        #[arg(long, default_value = "0")]
+        offset: usize,
+        /// Continue after the page that printed this `next_cursor`.
+        #[arg(long, conflicts_with = "offset")]
+        cursor: Option<String>,
         /// Enable explain mode.
         #[arg(long, default_value_t = false)]
         explain: bool,
@@ -75,9 +135,361 @@ enum Command {
         /// Maximum number of pages to print when document text is requested.
         #[arg(long, default_value = "2")]
         document_text_max_pages: usize,
+        /// Drop OCR chunks whose confidence is below this value (0.0-1.0).
+        #[arg(long)]
+        min_ocr_confidence: Option<f32>,
+        /// Scale scores of OCR chunks by their OCR confidence.
+        #[arg(long, default_value_t = false)]
+        downweight_ocr: bool,
+        /// How backend rankings are combined: rrf, combsum, combmnz or keyword-first.
+        #[arg(long, default_value = "rrf")]
+        fusion: String,
+        /// Score normalisation for combsum/combmnz: min-max or z-score.
+        #[arg(long, default_value = "min-max")]
+        normalization: ScoreNormalization,
+        /// RRF rank constant.
+        #[arg(long, default_value = "60")]
+        rrf_k: f64,
+        /// Backends to query, comma separated, each optionally with a fusion weight (`keyword=0.7`).
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_name = "MODE[=WEIGHT]",
+            default_value = "keyword,vector,graph"
+        )]
+        modes: Vec<ModeWeight>,
+        /// Milliseconds to wait for OpenSearch before searching without it.
+        #[arg(long, default_value = "10000")]
+        keyword_timeout_ms: u64,
+        /// Milliseconds to wait for Qdrant before searching without it.
+        #[arg(long, default_value = "10000")]
+        vector_timeout_ms: u64,
+        /// Milliseconds to wait for Neo4j graph expansion before skipping it.
+        #[arg(long, default_value = "5000")]
+        graph_timeout_ms: u64,
+        /// Rescore the best fused hits before truncation: lexical or http.
+        #[arg(long)]
+        rerank: Option<String>,
+        /// Rerank server URL for `--rerank http` (text-embeddings-inference `/rerank` API).
+        #[arg(long, env = "DOCNEEDLE_RERANK_ENDPOINT")]
+        rerank_endpoint: Option<String>,
+        /// Number of fused hits passed to the reranker.
+        #[arg(long, default_value_t = DEFAULT_RERANK_TOP_N)]
+        rerank_top_n: usize,
+        /// Milliseconds to wait for the rerank server before keeping the fused order.
+        #[arg(long, default_value = "10000")]
+        rerank_timeout_ms: u64,
+        /// Show only the best chunk of each document.
+        #[arg(long, default_value_t = false)]
+        collapse_documents: bool,
+        /// Diversify results with MMR: 1.0 ranks purely by relevance, lower values favour novel chunks.
+        #[arg(long, value_name = "LAMBDA")]
+        diversity: Option<f64>,
+        /// Print this many neighbouring chunks before and after each hit.
+        #[arg(long, value_name = "N", default_value = "0")]
+        context: usize,
+        /// Print the whole chunk text instead of highlighted snippets.
+        #[arg(long, default_value_t = false)]
+        full_text: bool,
+    },
+    /// Watch a folder and keep all stores in sync as documents are added, changed or removed.
+    Watch {
+        /// Folder that contains documents recursively.
+        #[arg(long)]
+        folder: String,
+        /// Quiet period before changed files are ingested, in milliseconds.
+        #[arg(long, default_value = "2000")]
+        debounce_ms: u64,
+        /// Where checksums of indexed files are kept between runs.
+        #[arg(long, default_value = ".docneedle/watch-state.json")]
+        state_file: String,
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+    },
+    /// Remove cached OCR results.
+    PruneOcrCache {
+        /// Only remove entries older than this many days; removes everything when omitted.
+        #[arg(long)]
+        older_than_days: Option<u64>,
     },
 }
 
+#[derive(Clone, Args)]
+struct DiscoveryArgs {
+    /// Only ingest files matching this glob, relative to the folder (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+    /// Skip files and directories matching this glob (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Skip files larger than this many MiB
+    #[arg(long)]
+    max_file_mb: Option<u64>,
+    /// Maximum directory depth below the folder
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Follow symbolic links while walking the folder
+    #[arg(long, default_value_t = false)]
+    follow_symlinks: bool,
+    /// Skip hidden files and directories
+    #[arg(long, default_value_t = false)]
+    skip_hidden: bool,
+    /// Ignore `.docneedleignore` files
+    #[arg(long, default_value_t = false)]
+    no_ignore_files: bool,
+}
+
+impl DiscoveryArgs {
+    fn policy(&self) -> DiscoveryPolicy {
+        DiscoveryPolicy {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            max_file_bytes: self.max_file_mb.map(|mb| mb * 1024 * 1024),
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+            include_hidden: !self.skip_hidden,
+            use_ignore_files: !self.no_ignore_files,
+        }
+    }
+}
+
+struct Stores {
+    keyword: OpenSearchStore,
+    vector: QdrantStore,
+    graph: Neo4jStore,
+}
+
+impl Stores {
+    fn new(cli: &Cli, dimensions: usize) -> Self {
+        Self {
+            keyword: OpenSearchStore::new(&cli.opensearch_url, &cli.opensearch_index),
+            vector: QdrantStore::new(&cli.qdrant_url, &cli.qdrant_collection, dimensions),
+            graph: Neo4jStore::new(
+                &cli.neo4j_url,
+                &cli.neo4j_db,
+                &cli.neo4j_user,
+                &cli.neo4j_password,
+            ),
+        }
+    }
+
+    async fn prepare(&self) -> anyhow::Result<()> {
+        self.keyword
+            .ensure_index()
+            .await
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn index(
+        &self,
+        embedder: &CharacterNgramEmbedder,
+        chunks: &[PdfChunk],
+    ) -> anyhow::Result<()> {
+        let embeddings: Vec<_> = chunks
+            .iter()
+            .map(|chunk| embedder.embed(&chunk.text_normalized))
+            .collect();
+
+        self.vector
+            .ensure_collection(embedder.dimensions())
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        self.keyword
+            .index_keyword_chunks(chunks)
+            .await
+            .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
+        self.vector
+            .index_vector_chunks(chunks, &embeddings)
+            .await
+            .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
+        self.graph
+            .sync_graph_relations(chunks)
+            .await
+            .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete(&self, document_ids: &[String]) -> anyhow::Result<()> {
+        self.keyword
+            .delete_keyword_documents(document_ids)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        self.vector
+            .delete_vector_documents(document_ids)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        self.graph
+            .delete_graph_documents(document_ids)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// How long `watch` waits before retrying a failed scan or sync.
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+struct WatchedStores<'a> {
+    stores: &'a Stores,
+    embedder: &'a CharacterNgramEmbedder,
+}
+
+#[async_trait]
+impl WatchTarget for WatchedStores<'_> {
+    async fn delete_documents(&self, document_ids: &[String]) -> Result<(), SearchError> {
+        self.stores
+            .delete(document_ids)
+            .await
+            .map_err(|error| SearchError::Request(error.to_string()))
+    }
+
+    async fn index_chunks(&self, chunks: &[PdfChunk]) -> Result<(), SearchError> {
+        self.stores
+            .index(self.embedder, chunks)
+            .await
+            .map_err(|error| SearchError::Request(error.to_string()))
+    }
+}
+
+/// Extra `GLOB=PASSWORD` entries, one per line. Passwords are read from the
+/// environment or the keyring file rather than arguments, which `ps` and shell
+/// history can see.
+const PDF_PASSWORDS_ENV: &str = "DOCNEEDLE_PDF_PASSWORDS";
+
+fn load_passwords(cli: &Cli) -> anyhow::Result<PasswordMap> {
+    let mut passwords = match &cli.password_file {
+        Some(path) => PasswordMap::from_keyring_file(Path::new(path))
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?,
+        None => PasswordMap::new(),
+    };
+
+    let entries = std::env::var(PDF_PASSWORDS_ENV).unwrap_or_default();
+    for entry in entries.lines().filter(|entry| !entry.trim().is_empty()) {
+        let (pattern, password) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("{PDF_PASSWORDS_ENV} expects GLOB=PASSWORD lines"))?;
+        passwords
+            .insert(pattern, password)
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+    }
+
+    Ok(passwords)
+}
+
+fn extractor_chain(cli: &Cli) -> ExtractorChain {
+    let text_layer = PageAcceptance {
+        min_chars: cli.min_page_chars,
+        ..PageAcceptance::default()
+    };
+    let chain = if cli.layout {
+        ExtractorChain::new().then(LayoutExtractor::default(), text_layer)
+    } else {
+        ExtractorChain::new().then(LopdfExtractor::default(), text_layer)
+    };
+
+    if cli.local_ocr {
+        chain.then(
+            OcrExtractor::new(TesseractConfig {
+                languages: cli.ocr_languages.clone(),
+                dpi: cli.ocr_dpi,
+                ..TesseractConfig::default()
+            }),
+            PageAcceptance::any(),
+        )
+    } else if let Some(llm_ocr) = LlmOcrExtractor::from_env() {
+        chain.then(llm_ocr, PageAcceptance::any())
+    } else {
+        chain
+    }
+}
+
+fn ingestion_options(cli: &Cli) -> anyhow::Result<IngestionOptions> {
+    Ok(IngestionOptions {
+        passwords: load_passwords(cli)?,
+        extractors: extractor_chain(cli),
+        ocr_cache: (!cli.no_ocr_cache).then(|| OcrCache::new(&cli.ocr_cache_dir)),
+        sources: SourceRegistry::for_formats(&cli.formats),
+        expand_archives: cli.expand_archives,
+        ..IngestionOptions::default()
+    })
+}
+
+/// Prints a neighbouring chunk, dimmed when writing to a terminal.
+fn print_context(chunk: &ContextChunk, dim: bool) {
+    let label = format!(
+        "  context chunk={} index={}:",
+        chunk.chunk_id, chunk.chunk_index
+    );
+    if dim {
+        println!("\x1b[2m{label}\n{}\x1b[0m", chunk.text);
+    } else {
+        println!("{label}\n{}", chunk.text);
+    }
+}
+
+/// Prints a snippet with its matches in bold on a terminal, `**marked**`
+/// otherwise.
+fn print_highlight(highlight: &Highlight, terminal: bool) {
+    let (open, close) = if terminal {
+        ("\x1b[1m", "\x1b[0m")
+    } else {
+        ("**", "**")
+    };
+    let mut line = String::new();
+    let mut written = 0;
+    for &(start, end) in &highlight.matches {
+        let (Some(before), Some(matched)) = (
+            highlight.fragment.get(written..start),
+            highlight.fragment.get(start..end),
+        ) else {
+            continue;
+        };
+        line.push_str(before);
+        line.push_str(open);
+        line.push_str(matched);
+        line.push_str(close);
+        written = end;
+    }
+    line.push_str(highlight.fragment.get(written..).unwrap_or_default());
+    println!("  snippet: …{}…", line.trim());
+}
+
+fn print_explanation(explanation: &Explanation) {
+    println!(
+        "  explain: fusion={} fused_score={:.6}",
+        explanation.fusion, explanation.fused_score
+    );
+    for mode in &explanation.modes {
+        println!(
+            "    {} rank={} raw={:.4} contribution={:.6}",
+            mode.mode, mode.rank, mode.raw_score, mode.contribution
+        );
+    }
+    if !explanation.matched_terms.is_empty() {
+        println!("    matched: {}", explanation.matched_terms.join(", "));
+    }
+    if !explanation.excluded_terms.is_empty() {
+        println!("    excluded: {}", explanation.excluded_terms.join(", "));
+    }
+    if !explanation.filters.is_empty() {
+        println!("    filters: {}", explanation.filters.join(", "));
+    }
+    if let Some(factor) = explanation.ocr_factor {
+        println!("    ocr_factor={factor:.2}");
+    }
+    if let Some(score) = explanation.rerank_score {
+        println!("    rerank_score={score:.6}");
+    }
+    if !explanation.graph_path.is_empty() {
+        println!("    graph: {}", explanation.graph_path.join(" -> "));
+    }
+    if let Some(keyword) = &explanation.keyword_explanation {
+        let value = keyword["value"].as_f64().unwrap_or_default();
+        let description = keyword["description"].as_str().unwrap_or_default();
+        println!("    keyword: {value:.4} {description}");
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let app_version = env!("CARGO_PKG_VERSION");
@@ -90,19 +502,11 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     let query_embedder = CharacterNgramEmbedder::default();
-    let keyword = OpenSearchStore::new(&cli.opensearch_url, &cli.opensearch_index);
-    let vector = QdrantStore::new(
-        &cli.qdrant_url,
-        &cli.qdrant_collection,
-        query_embedder.dimensions(),
-    );
-    let graph = Neo4jStore::new(
-        &cli.neo4j_url,
-        &cli.neo4j_db,
-        &cli.neo4j_user,
-        &cli.neo4j_password,
-    );
-
+    let Stores {
+        keyword,
+        vector,
+        graph,
+    } = Stores::new(&cli, query_embedder.dimensions());
     let coordinator = SearchCoordinator::new(keyword, vector, graph);
     info!(
         version = app_version,
@@ -110,12 +514,24 @@ async fn main() -> anyhow::Result<()> {
         "pdf-search-engine boot"
     );
 
-    match cli.command {
-        Command::Ingest { folder } => {
+    let stores = Stores::new(&cli, query_embedder.dimensions());
+
+    // Ingestion options read the password keyring and OCR settings, so they
+    // are only built for the commands that open documents.
+    match cli.command.clone() {
+        Command::Ingest {
+            folder,
+            report_dir,
+            discovery,
+        } => {
             let path = std::path::Path::new(&folder);
-            let report = ingest_folder_chunks_best_effort(path, IngestionOptions::default())
+            let options = IngestionOptions {
+                discovery: discovery.policy(),
+                ..ingestion_options(&cli)?
+            };
+            let mut report = ingest_folder_chunks_best_effort(path, options)
                 .map_err(|error| anyhow::anyhow!(error.to_string()))?;
-            let chunks = report.chunks;
+            let chunks = std::mem::take(&mut report.chunks);
 
             if !report.skipped_files.is_empty() {
                 warn!(
@@ -123,55 +539,55 @@ async fn main() -> anyhow::Result<()> {
                     report.skipped_files.len(),
                     folder
                 );
-                for skipped in report.skipped_files {
-                    warn!(path = %skipped.path.display(), reason = %skipped.reason, "skipped pdf");
+                for skipped in &report.skipped_files {
+                    warn!(
+                        path = %skipped.path.display(),
+                        category = ?skipped.category,
+                        reason = %skipped.reason,
+                        "skipped pdf"
+                    );
                 }
             }
 
+            for unreadable in &report.unreadable_paths {
+                warn!(
+                    path = %unreadable.path.display(),
+                    reason = %unreadable.reason,
+                    "unreadable path"
+                );
+            }
+
+            if let Some(stats) = &report.ocr_cache {
+                info!(
+                    hits = stats.hits,
+                    misses = stats.misses,
+                    writes = stats.writes,
+                    "ocr cache"
+                );
+            }
+
             if chunks.is_empty() {
                 println!("0 chunks ingested (all files were skipped)");
             }
 
             info!(folder=%folder, chunk_count=%chunks.len(), "ingesting chunks");
 
-            let embeddings: Vec<_> = chunks
-                .iter()
-                .map(|chunk| query_embedder.embed(&chunk.text_normalized))
-                .collect();
-
-            let keyword_store = OpenSearchStore::new(&cli.opensearch_url, &cli.opensearch_index);
-            let vector_store = QdrantStore::new(
-                &cli.qdrant_url,
-                &cli.qdrant_collection,
-                query_embedder.dimensions(),
-            );
-            let graph_store = Neo4jStore::new(
-                &cli.neo4j_url,
-                &cli.neo4j_db,
-                &cli.neo4j_user,
-                &cli.neo4j_password,
-            );
-
-            keyword_store
-                .ensure_index()
-                .await
-                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
-            vector_store
-                .ensure_collection(query_embedder.dimensions())
+            let indexed = async {
+                stores.prepare().await?;
+                stores.index(&query_embedder, &chunks).await
+            }
+            .await;
+            report.indexing = match &indexed {
+                Ok(()) => IndexingStatus::Indexed,
+                Err(error) => IndexingStatus::Failed {
+                    error: error.to_string(),
+                },
+            };
+            let report_path = report
+                .write_json_in(Path::new(&report_dir))
                 .map_err(|error| anyhow::anyhow!(error.to_string()))?;
-
-            keyword_store
-                .index_keyword_chunks(&chunks)
-                .await
-                .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
-            vector_store
-                .index_vector_chunks(&chunks, &embeddings)
-                .await
-                .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
-            graph_store
-                .sync_graph_relations(&chunks)
-                .await
-                .map_err(|error: SearchError| anyhow::anyhow!(error.to_string()))?;
+            info!(report = %report_path.display(), "ingestion report written");
+            indexed?;
 
             println!(
                 "{} chunks ingested at {}",
@@ -182,25 +598,71 @@ async fn main() -> anyhow::Result<()> {
         Command::Search {
             query,
             top_k,
+            offset,
+            cursor,
             explain,
             include_document_text,
             document_text_max_pages,
+            min_ocr_confidence,
+            downweight_ocr,
+            fusion,
+            normalization,
+            rrf_k,
+            modes,
+            keyword_timeout_ms,
+            vector_timeout_ms,
+            graph_timeout_ms,
+            rerank,
+            rerank_endpoint,
+            rerank_top_n,
+            rerank_timeout_ms,
+            collapse_documents,
+            diversity,
+            context,
+            full_text,
         } => {
             let search_query = SearchQuery {
-                text: query,
                 top_k,
-                mandatory_terms: Vec::new(),
-                must_not_terms: Vec::new(),
-                filters: Default::default(),
+                offset,
+                cursor,
                 explain,
+                min_ocr_confidence,
+                downweight_ocr,
+                collapse_documents,
+                diversity,
+                context_chunks: context,
+                fusion: Fusion::from_name(&fusion, normalization, rrf_k)
+                    .map_err(|error| anyhow::anyhow!(error.to_string()))?,
+                modes,
+                rerank: rerank
+                    .map(|name| {
+                        Rerank::from_name(
+                            &name,
+                            rerank_endpoint.as_deref(),
+                            rerank_top_n,
+                            Duration::from_millis(rerank_timeout_ms),
+                        )
+                    })
+                    .transpose()
+                    .map_err(|error| anyhow::anyhow!(error.to_string()))?,
+                ..SearchQuery::parse(&query).map_err(|error| anyhow::anyhow!(error.to_string()))?
             };
 
             let result = coordinator
+                .with_timeouts(BackendTimeouts {
+                    keyword: Duration::from_millis(keyword_timeout_ms),
+                    vector: Duration::from_millis(vector_timeout_ms),
+                    graph: Duration::from_millis(graph_timeout_ms),
+                })
                 .search(&search_query)
                 .await
                 .map_err(|error| anyhow::anyhow!(error.to_string()))?;
 
             println!("query: {}", result.query);
+            for warning in &result.warnings {
+                println!("warning: {warning}");
+            }
+            let terminal = std::io::stdout().is_terminal();
             let mut emitted_documents: HashSet<String> = HashSet::new();
             let mut document_order: Vec<String> = Vec::new();
 
@@ -212,8 +674,58 @@ async fn main() -> anyhow::Result<()> {
                 if !hit.source_path.is_empty() {
                     println!("  source={}", hit.source_path);
                 }
-                if let Some(text) = &hit.text {
-                    println!("  chunk_text:\n{text}");
+                if hit.collapsed > 0 {
+                    println!("  ({} more in this document)", hit.collapsed);
+                }
+                if let Some(citation) = &hit.citation {
+                    println!("  citation: {citation}");
+                    for span in &citation.page_spans {
+                        match span.bbox {
+                            Some(bbox) => println!(
+                                "    p.{} chars {}..{} bbox=[{:.1}, {:.1}, {:.1}, {:.1}]",
+                                span.page,
+                                span.char_start,
+                                span.char_end,
+                                bbox.x0,
+                                bbox.y0,
+                                bbox.x1,
+                                bbox.y1
+                            ),
+                            None => println!(
+                                "    p.{} chars {}..{}",
+                                span.page, span.char_start, span.char_end
+                            ),
+                        }
+                    }
+                }
+                if let Some(confidence) = hit.ocr_confidence {
+                    println!("  ocr_confidence={confidence:.2}");
+                }
+                if let Some(explanation) = &hit.explanation {
+                    print_explanation(explanation);
+                }
+                for neighbour in &hit.context_before {
+                    print_context(neighbour, terminal);
+                }
+                match &hit.text {
+                    Some(text) if full_text => println!("  chunk_text:\n{text}"),
+                    Some(text) if hit.highlights.is_empty() => {
+                        let snippet = text.chars().take(SNIPPET_CHARS).collect::<String>();
+                        let more = if snippet.len() < text.len() {
+                            "…"
+                        } else {
+                            ""
+                        };
+                        println!("  snippet: {snippet}{more}");
+                    }
+                    _ => {
+                        for highlight in &hit.highlights {
+                            print_highlight(highlight, terminal);
+                        }
+                    }
+                }
+                for neighbour in &hit.context_after {
+                    print_context(neighbour, terminal);
                 }
                 if include_document_text
                     && !hit.source_path.is_empty()
@@ -223,10 +735,23 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
 
+            if let Some(next_cursor) = &result.next_cursor {
+                println!("next_cursor: {next_cursor}");
+            }
+
             if include_document_text {
+                let options = ingestion_options(&cli).map_err(|error| error.to_string());
                 for path in document_order {
                     println!("document_text: path={path}");
-                    match extract_page_texts(Path::new(&path)) {
+                    let extracted = options.as_ref().map_err(Clone::clone).and_then(|options| {
+                        resolve_source_path(Path::new(&path), options)
+                            .and_then(|resolved| match options.sources.for_path(&resolved.path) {
+                                Some(source) => source.extract(&resolved.path, options),
+                                None => extract_page_texts(&resolved.path, options),
+                            })
+                            .map_err(|error| error.to_string())
+                    });
+                    match extracted {
                         Ok(pages) => {
                             for (index, page) in pages.iter().enumerate() {
                                 if index >= document_text_max_pages {
@@ -249,11 +774,112 @@ async fn main() -> anyhow::Result<()> {
             }
 
             if explain {
+                println!("explain: fusion={}", search_query.fusion);
+                if let Some(rerank) = &search_query.rerank {
+                    println!("explain: rerank={rerank}");
+                }
                 for (mode, k, score) in result.mode_scores {
                     println!("explain: mode={mode} top_k={k} weight={score:.2}");
                 }
             }
         }
+        Command::Watch {
+            folder,
+            debounce_ms,
+            state_file,
+            discovery,
+        } => {
+            let folder = Path::new(&folder);
+            let options = IngestionOptions {
+                discovery: discovery.policy(),
+                ..ingestion_options(&cli)?
+            };
+            let state_file = Path::new(&state_file);
+            let mut indexed = IndexedFiles::load(state_file)
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+            stores.prepare().await?;
+
+            let watcher = FolderWatcher::new(folder, Duration::from_millis(debounce_ms))
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+            info!(folder = %folder.display(), indexed = indexed.len(), "watching folder");
+
+            let target = WatchedStores {
+                stores: &stores,
+                embedder: &query_embedder,
+            };
+            let mut busy = HashSet::new();
+            loop {
+                // Failures are logged and retried after a pause instead of
+                // ending the watcher; the change is only recorded once indexed.
+                let mut failed = false;
+                match indexed.scan(folder, &options, &busy) {
+                    Err(error) => {
+                        warn!(%error, "scanning watched folder failed");
+                        failed = true;
+                    }
+                    Ok(changes) if !changes.is_empty() => {
+                        let report = tokio::task::block_in_place(|| {
+                            ingest_discovered(folder, changes.changed.clone(), options.clone())
+                        });
+                        match indexed.sync(&changes, &report, &target).await {
+                            Err(error) => {
+                                warn!(%error, "synchronising watched folder failed");
+                                failed = true;
+                            }
+                            Ok(()) => {
+                                if let Err(error) = indexed.save(state_file) {
+                                    warn!(%error, "saving watch state failed");
+                                }
+                                for skipped in &report.skipped_files {
+                                    warn!(
+                                        path = %skipped.path.display(),
+                                        category = ?skipped.category,
+                                        reason = %skipped.reason,
+                                        "skipped document"
+                                    );
+                                }
+                                info!(
+                                    changed = changes.changed.files.len(),
+                                    removed = changes.removed.len(),
+                                    chunk_count = report.chunks.len(),
+                                    "folder synchronised"
+                                );
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                }
+                if failed {
+                    tokio::time::sleep(WATCH_RETRY_DELAY).await;
+                    continue;
+                }
+
+                // Wait for a batch that settled at least one path; batches that
+                // only saw ongoing writes are followed by a settled one.
+                let batch = loop {
+                    let batch = match tokio::task::block_in_place(|| watcher.next_batch()) {
+                        Some(batch) => batch.map_err(|error| anyhow::anyhow!(error.to_string()))?,
+                        None => return Ok(()),
+                    };
+                    if !batch.settled.is_empty() {
+                        break batch;
+                    }
+                };
+                busy = batch.busy;
+            }
+        }
+        Command::PruneOcrCache { older_than_days } => {
+            let cache = OcrCache::new(&cli.ocr_cache_dir);
+            let stats = cache
+                .prune(older_than_days.map(|days| Duration::from_secs(days * 24 * 60 * 60)))
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+            println!(
+                "removed {} cached OCR page(s), {} bytes from {}",
+                stats.removed_entries,
+                stats.freed_bytes,
+                cache.root().display()
+            );
+        }
     }
 
     Ok(())
@@ -0,0 +1,405 @@
+use crate::error::IngestError;
+use crate::extractor::ScratchDir;
+use crate::models::IngestionOptions;
+use flate2::read::GzDecoder;
+use mail_parser::{MessageParser, MimeHeaders};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+pub const VIRTUAL_SEPARATOR: &str = "!/";
+const MAX_NESTING: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Zip,
+    Tar,
+    TarGz,
+    Email,
+}
+
+pub fn container_kind(path: &Path) -> Option<ContainerKind> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(ContainerKind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(ContainerKind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ContainerKind::TarGz)
+    } else if name.ends_with(".eml") {
+        Some(ContainerKind::Email)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerMember {
+    pub virtual_path: PathBuf,
+    pub path: PathBuf,
+}
+
+/// Members of an archive or message, unpacked into a scratch directory that
+/// lives as long as this value.
+pub struct ExpandedContainer {
+    _scratch: ScratchDir,
+    pub members: Vec<ContainerMember>,
+}
+
+pub fn expand_container(
+    path: &Path,
+    virtual_path: &Path,
+    options: &IngestionOptions,
+) -> Result<ExpandedContainer, IngestError> {
+    let scratch = ScratchDir::create()?;
+    let mut expansion = Expansion {
+        options,
+        remaining_bytes: options.archive_max_bytes,
+        members: Vec::new(),
+        next_dir: 0,
+    };
+    expansion.expand(path, virtual_path, scratch.path(), 0)?;
+
+    Ok(ExpandedContainer {
+        _scratch: scratch,
+        members: expansion.members,
+    })
+}
+
+/// A readable path for `source_path`, unpacking `bundle.zip!/manuals/pump.pdf`
+/// style virtual paths on demand.
+pub struct ResolvedSource {
+    _container: Option<ExpandedContainer>,
+    pub path: PathBuf,
+}
+
+pub fn resolve_source_path(
+    source_path: &Path,
+    options: &IngestionOptions,
+) -> Result<ResolvedSource, IngestError> {
+    let text = source_path.to_string_lossy();
+    let Some((container, _)) = text.split_once(VIRTUAL_SEPARATOR) else {
+        return Ok(ResolvedSource {
+            _container: None,
+            path: source_path.to_path_buf(),
+        });
+    };
+
+    let expanded = expand_container(Path::new(container), Path::new(container), options)?;
+    let path = expanded
+        .members
+        .iter()
+        .find(|member| member.virtual_path == source_path)
+        .map(|member| member.path.clone())
+        .ok_or_else(|| IngestError::InvalidArgument(format!("{} is not in {container}", text)))?;
+
+    Ok(ResolvedSource {
+        _container: Some(expanded),
+        path,
+    })
+}
+
+struct Expansion<'a> {
+    options: &'a IngestionOptions,
+    remaining_bytes: u64,
+    members: Vec<ContainerMember>,
+    next_dir: usize,
+}
+
+impl Expansion<'_> {
+    fn expand(
+        &mut self,
+        path: &Path,
+        virtual_path: &Path,
+        scratch: &Path,
+        depth: usize,
+    ) -> Result<(), IngestError> {
+        let kind = container_kind(path).ok_or_else(|| {
+            IngestError::Unsupported(format!("{} is not an archive", path.display()))
+        })?;
+        let dir = scratch.join(format!("{}", self.next_dir));
+        self.next_dir += 1;
+        fs::create_dir_all(&dir)?;
+
+        let unpacked = match kind {
+            ContainerKind::Zip => self.unzip(path, &dir)?,
+            ContainerKind::Tar => self.untar(File::open(path)?, path, &dir)?,
+            ContainerKind::TarGz => self.untar(GzDecoder::new(File::open(path)?), path, &dir)?,
+            ContainerKind::Email => self.detach(path, &dir)?,
+        };
+
+        for (relative, written) in unpacked {
+            let member = PathBuf::from(format!(
+                "{}{VIRTUAL_SEPARATOR}{}",
+                virtual_path.display(),
+                relative.display()
+            ));
+
+            if container_kind(&written).is_some() {
+                if depth + 1 < MAX_NESTING {
+                    self.expand(&written, &member, scratch, depth + 1)?;
+                }
+            } else if self.options.sources.for_path(&written).is_some() {
+                self.members.push(ContainerMember {
+                    virtual_path: member,
+                    path: written,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn wanted(&self, relative: &Path) -> bool {
+        relative.extension().is_none()
+            || container_kind(relative).is_some()
+            || self.options.sources.for_path(relative).is_some()
+    }
+
+    fn write_member(
+        &mut self,
+        reader: &mut dyn Read,
+        size: u64,
+        relative: &Path,
+        dir: &Path,
+        container: &Path,
+    ) -> Result<PathBuf, IngestError> {
+        if size > self.remaining_bytes {
+            return Err(IngestError::TooLarge(format!(
+                "{} unpacks to more than {} bytes",
+                container.display(),
+                self.options.archive_max_bytes
+            )));
+        }
+
+        let target = dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let written = io::copy(
+            &mut reader.take(self.remaining_bytes + 1),
+            &mut File::create(&target)?,
+        )?;
+        if written > self.remaining_bytes {
+            return Err(IngestError::TooLarge(format!(
+                "{} unpacks to more than {} bytes",
+                container.display(),
+                self.options.archive_max_bytes
+            )));
+        }
+        self.remaining_bytes -= written;
+        Ok(target)
+    }
+
+    fn unzip(&mut self, path: &Path, dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>, IngestError> {
+        let corrupt = |error: zip::result::ZipError| {
+            IngestError::Corrupt(format!("{}: {error}", path.display()))
+        };
+        let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(corrupt)?;
+        let mut unpacked = Vec::new();
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(corrupt)?;
+            let Some(relative) = entry.enclosed_name() else {
+                continue;
+            };
+            if entry.is_dir() || !self.wanted(&relative) {
+                continue;
+            }
+
+            let size = entry.size();
+            let written = self.write_member(&mut entry, size, &relative, dir, path)?;
+            unpacked.push((relative, written));
+        }
+
+        Ok(unpacked)
+    }
+
+    fn untar(
+        &mut self,
+        reader: impl Read,
+        path: &Path,
+        dir: &Path,
+    ) -> Result<Vec<(PathBuf, PathBuf)>, IngestError> {
+        let corrupt =
+            |error: io::Error| IngestError::Corrupt(format!("{}: {error}", path.display()));
+        let mut archive = tar::Archive::new(reader);
+        let mut unpacked = Vec::new();
+
+        for entry in archive.entries().map_err(corrupt)? {
+            let mut entry = entry.map_err(corrupt)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().map_err(corrupt)?.into_owned();
+            if !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+                || !self.wanted(&relative)
+            {
+                continue;
+            }
+
+            let size = entry.size();
+            let written = self.write_member(&mut entry, size, &relative, dir, path)?;
+            unpacked.push((relative, written));
+        }
+
+        Ok(unpacked)
+    }
+
+    fn detach(&mut self, path: &Path, dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>, IngestError> {
+        let raw = fs::read(path)?;
+        let message = MessageParser::default().parse(&raw).ok_or_else(|| {
+            IngestError::Corrupt(format!("{} is not a MIME message", path.display()))
+        })?;
+        let mut unpacked = Vec::new();
+
+        for (index, part) in message.attachments().enumerate() {
+            let name = part
+                .attachment_name()
+                .and_then(|name| Path::new(name).file_name())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    let extension = if part.is_message() { "eml" } else { "bin" };
+                    PathBuf::from(format!("attachment-{}.{extension}", index + 1))
+                });
+            if !self.wanted(&name) {
+                continue;
+            }
+
+            let relative = if unpacked.iter().any(|(existing, _)| existing == &name) {
+                PathBuf::from(format!("{}-{}", index + 1, name.display()))
+            } else {
+                name
+            };
+            let contents = part.contents();
+            let written = self.write_member(
+                &mut &contents[..],
+                contents.len() as u64,
+                &relative,
+                dir,
+                path,
+            )?;
+            unpacked.push((relative, written));
+        }
+
+        Ok(unpacked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_container, resolve_source_path};
+    use crate::test_pdf::build_pdf;
+    use crate::{FailureCategory, IngestionOptions, SourceRegistry};
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    fn pdf_bytes(text: &str) -> Vec<u8> {
+        let mut pdf = Vec::new();
+        build_pdf(&[text])
+            .save_to(&mut pdf)
+            .expect("test pdf should serialize");
+        pdf
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).expect("zip file"));
+        for (name, contents) in entries {
+            zip.start_file(*name, SimpleFileOptions::default())
+                .expect("zip entry");
+            zip.write_all(contents).expect("zip contents");
+        }
+        zip.finish().expect("zip finish");
+    }
+
+    #[test]
+    fn zip_members_get_virtual_paths() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let bundle = dir.path().join("bundle.zip");
+        let pdf = pdf_bytes("Pump manual");
+        write_zip(
+            &bundle,
+            &[
+                ("manuals/pump.pdf", &pdf),
+                ("images/photo.png", b"\x89PNG"),
+                ("../escape.pdf", &pdf),
+            ],
+        );
+
+        let options = IngestionOptions::default();
+        let expanded = expand_container(&bundle, &bundle, &options)?;
+
+        assert_eq!(expanded.members.len(), 1);
+        let virtual_path = PathBuf::from(format!("{}!/manuals/pump.pdf", bundle.display()));
+        assert_eq!(expanded.members[0].virtual_path, virtual_path);
+        assert_eq!(std::fs::read(&expanded.members[0].path)?, pdf);
+
+        let resolved = resolve_source_path(&virtual_path, &options)?;
+        assert_eq!(std::fs::read(&resolved.path)?, pdf);
+        Ok(())
+    }
+
+    #[test]
+    fn email_attachments_and_nested_archives_are_expanded() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let dir = tempdir()?;
+        let inner = dir.path().join("inner.zip");
+        write_zip(&inner, &[("datasheet.pdf", &pdf_bytes("Datasheet"))]);
+        let inner_base64 = {
+            use base64::Engine;
+            base64::engine::general_purpose::STANDARD.encode(std::fs::read(&inner)?)
+        };
+        let message = dir.path().join("vendor.eml");
+        std::fs::write(
+            &message,
+            format!(
+                "From: vendor@example.com\r\nSubject: Docs\r\nMIME-Version: 1.0\r\n\
+                 Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n\
+                 --b\r\nContent-Type: text/plain\r\n\r\nSee attached.\r\n\
+                 --b\r\nContent-Type: application/zip\r\n\
+                 Content-Disposition: attachment; filename=\"docs.zip\"\r\n\
+                 Content-Transfer-Encoding: base64\r\n\r\n{inner_base64}\r\n--b--\r\n"
+            ),
+        )?;
+
+        let expanded = expand_container(
+            &message,
+            Path::new("vendor.eml"),
+            &IngestionOptions::default(),
+        )?;
+
+        assert_eq!(
+            expanded
+                .members
+                .iter()
+                .map(|member| member.virtual_path.clone())
+                .collect::<Vec<_>>(),
+            vec![PathBuf::from("vendor.eml!/docs.zip!/datasheet.pdf")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_archives_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let bundle = dir.path().join("bomb.zip");
+        write_zip(&bundle, &[("big.txt", &vec![b'a'; 4096])]);
+
+        let options = IngestionOptions {
+            archive_max_bytes: 1024,
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+        let error = expand_container(&bundle, &bundle, &options)
+            .map(|_| ())
+            .expect_err("archive exceeds the limit");
+
+        assert_eq!(error.category(), FailureCategory::TooLarge);
+        Ok(())
+    }
+}
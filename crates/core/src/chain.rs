@@ -0,0 +1,505 @@
+use crate::error::IngestError;
+use crate::extractor::{
+    open_document, subset_pdf, ExtractionMethod, LopdfExtractor, PageText, PdfExtractor,
+};
+use crate::ingest::digest_file;
+use crate::llm_ocr::LlmOcrExtractor;
+use crate::ocr_cache::OcrCache;
+use crate::passwords::PasswordMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageAcceptance {
+    pub min_chars: usize,
+    pub min_readable_ratio: f32,
+}
+
+impl Default for PageAcceptance {
+    fn default() -> Self {
+        Self {
+            min_chars: 16,
+            min_readable_ratio: 0.7,
+        }
+    }
+}
+
+impl PageAcceptance {
+    pub fn any() -> Self {
+        Self {
+            min_chars: 1,
+            min_readable_ratio: 0.0,
+        }
+    }
+
+    pub fn accepts(&self, text: &str) -> bool {
+        let stats = TextStats::of(text);
+        stats.alphanumeric >= self.min_chars && !stats.is_garbage(self.min_readable_ratio)
+    }
+}
+
+#[derive(Clone)]
+pub struct ExtractorStep {
+    pub extractor: Arc<dyn PdfExtractor + Send + Sync>,
+    pub acceptance: PageAcceptance,
+}
+
+#[derive(Clone)]
+pub struct ExtractorChain {
+    steps: Vec<ExtractorStep>,
+}
+
+impl fmt::Debug for ExtractorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|step| step.extractor.name()))
+            .finish()
+    }
+}
+
+/// Text layer only; unlike `standard`, independent of the environment.
+impl Default for ExtractorChain {
+    fn default() -> Self {
+        Self::new().then(LopdfExtractor::default(), PageAcceptance::default())
+    }
+}
+
+impl ExtractorChain {
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Text layer first, then the multimodal OCR endpoint when `LLM_OCR_ENDPOINT` is set.
+    pub fn standard(text_layer: PageAcceptance) -> Self {
+        let chain = Self::new().then(LopdfExtractor::default(), text_layer);
+        match LlmOcrExtractor::from_env() {
+            Some(llm_ocr) => chain.then(llm_ocr, PageAcceptance::any()),
+            None => chain,
+        }
+    }
+
+    pub fn then<E>(mut self, extractor: E, acceptance: PageAcceptance) -> Self
+    where
+        E: PdfExtractor + Send + Sync + 'static,
+    {
+        self.push(Arc::new(extractor), acceptance);
+        self
+    }
+
+    pub fn push(
+        &mut self,
+        extractor: Arc<dyn PdfExtractor + Send + Sync>,
+        acceptance: PageAcceptance,
+    ) {
+        self.steps.push(ExtractorStep {
+            extractor,
+            acceptance,
+        });
+    }
+
+    pub fn steps(&self) -> &[ExtractorStep] {
+        &self.steps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn extract(
+        &self,
+        path: &Path,
+        passwords: &PasswordMap,
+        cache: Option<&OcrCache>,
+    ) -> Result<Vec<PageText>, IngestError> {
+        let (document, parse_error) = match open_document(path, passwords) {
+            Ok(opened) => (Some(opened), None),
+            Err(error @ (IngestError::Corrupt(_) | IngestError::Unsupported(_))) => {
+                (None, Some(error))
+            }
+            Err(error) => return Err(error),
+        };
+
+        // Decrypted documents are only handed to steps as in-memory subsets.
+        let decrypted = matches!(document, Some((_, true)));
+        let document = document.map(|(document, _)| document);
+
+        let mut known_pages = document
+            .as_ref()
+            .map(|document| document.get_pages().into_keys().collect::<BTreeSet<_>>());
+        let mut accepted = BTreeMap::<u32, PageText>::new();
+        let mut rejected = BTreeMap::<u32, PageText>::new();
+        let mut failures = BTreeMap::<u32, String>::new();
+        let mut last_error = None;
+
+        let mut checksum = None;
+        for step in &self.steps {
+            let pending = known_pages.as_ref().map(|known| {
+                known
+                    .iter()
+                    .copied()
+                    .filter(|number| !accepted.contains_key(number))
+                    .collect::<Vec<_>>()
+            });
+            if pending.as_ref().is_some_and(Vec::is_empty) {
+                break;
+            }
+
+            let step_cache = match (cache, step.extractor.cache_identity()) {
+                (Some(cache), Some(backend)) => checksum
+                    .get_or_insert_with(|| digest_file(path).ok())
+                    .clone()
+                    .map(|checksum| (cache, checksum, backend)),
+                _ => None,
+            };
+
+            let mut pages = Vec::new();
+            let mut missing = pending.clone();
+            if let Some((cache, checksum, backend)) = &step_cache {
+                if let Some(missing) = missing.as_mut() {
+                    missing.retain(|&number| match cache.get(checksum, backend, number) {
+                        Some(page) => {
+                            pages.push(page);
+                            false
+                        }
+                        None => true,
+                    });
+                } else if let Some(cached) = cache.get_document(checksum, backend) {
+                    pages = cached;
+                    missing = Some(Vec::new());
+                }
+            }
+
+            let extracted = match (&missing, &document, &known_pages) {
+                (Some(missing), _, _) if missing.is_empty() => Ok(Vec::new()),
+                (Some(missing), Some(document), Some(known))
+                    if decrypted || missing.len() < known.len() =>
+                {
+                    subset_pdf(document, missing)
+                        .and_then(|pdf| step.extractor.extract_page_subset(&pdf, path, missing))
+                }
+                _ => step.extractor.extract_pages(path),
+            };
+
+            match extracted {
+                Ok(extracted) => {
+                    let complete = extracted.iter().all(|page| page.failure.is_none());
+                    if let Some((cache, checksum, backend)) = &step_cache {
+                        // The cache is an optimisation; a failed write only costs a re-run.
+                        // Failed pages are not cached so that the next run retries them.
+                        let recognised = extracted
+                            .iter()
+                            .filter(|page| page.failure.is_none())
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let _ = match &missing {
+                            None if complete => cache.put_document(checksum, backend, &recognised),
+                            _ => cache.put(checksum, backend, &recognised),
+                        };
+                    }
+                    pages.extend(extracted);
+                }
+                Err(error) => {
+                    for &number in missing.iter().flatten() {
+                        failures.insert(number, format!("{}: {error}", step.extractor.name()));
+                    }
+                    last_error = Some(error);
+                }
+            }
+
+            for page in pages {
+                if pending
+                    .as_ref()
+                    .is_some_and(|pending| !pending.contains(&page.number))
+                {
+                    continue;
+                }
+                known_pages
+                    .get_or_insert_with(BTreeSet::new)
+                    .insert(page.number);
+
+                if let Some(failure) = &page.failure {
+                    failures.insert(page.number, format!("{}: {failure}", step.extractor.name()));
+                } else if step.acceptance.accepts(&page.text) {
+                    rejected.remove(&page.number);
+                    failures.remove(&page.number);
+                    accepted.insert(page.number, page);
+                } else if rejected.get(&page.number).map_or(true, |best| {
+                    TextStats::of(&page.text).alphanumeric > TextStats::of(&best.text).alphanumeric
+                }) {
+                    rejected.insert(page.number, page);
+                }
+            }
+        }
+
+        let readable_ratio = PageAcceptance::default().min_readable_ratio;
+        let pages = known_pages
+            .unwrap_or_default()
+            .into_iter()
+            .map(|number| {
+                let failure = failures.remove(&number);
+                if let Some(page) = accepted.remove(&number) {
+                    return page;
+                }
+                let page = rejected
+                    .remove(&number)
+                    .map(|page| {
+                        if TextStats::of(&page.text).is_garbage(readable_ratio) {
+                            PageText {
+                                text: String::new(),
+                                spans: Vec::new(),
+                                ..page
+                            }
+                        } else {
+                            page
+                        }
+                    })
+                    .unwrap_or(PageText {
+                        number,
+                        text: String::new(),
+                        method: ExtractionMethod::TextLayer,
+                        confidence: None,
+                        section: None,
+                        spans: Vec::new(),
+                        failure: None,
+                    });
+                PageText { failure, ..page }
+            })
+            .collect::<Vec<_>>();
+
+        if pages.iter().all(|page| page.text.trim().is_empty()) {
+            return Err(parse_error.or(last_error).unwrap_or_else(|| {
+                IngestError::NoTextLayer(format!(
+                    "pdf had no readable page text and no extractor recovered any: {}",
+                    path.display()
+                ))
+            }));
+        }
+
+        Ok(pages)
+    }
+}
+
+struct TextStats {
+    total: usize,
+    readable: usize,
+    alphanumeric: usize,
+}
+
+impl TextStats {
+    fn of(text: &str) -> Self {
+        let mut stats = Self {
+            total: 0,
+            readable: 0,
+            alphanumeric: 0,
+        };
+
+        for character in text.chars().filter(|character| !character.is_whitespace()) {
+            stats.total += 1;
+            if character.is_alphanumeric() {
+                stats.alphanumeric += 1;
+                stats.readable += 1;
+            } else if character.is_ascii_punctuation() || "°±×–—§µ²³‘’“”•€£".contains(character)
+            {
+                stats.readable += 1;
+            }
+        }
+
+        stats
+    }
+
+    fn is_garbage(&self, min_readable_ratio: f32) -> bool {
+        self.total > 0 && (self.readable as f32) < (self.total as f32) * min_readable_ratio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExtractorChain, PageAcceptance};
+    use crate::extractor::{ExtractionMethod, LopdfExtractor, PageText, PdfExtractor};
+    use crate::test_pdf::{build_pdf, encrypt_rc4, write_pdf};
+    use crate::{FailureCategory, IngestError, OcrCache, PasswordMap};
+    use std::path::Path;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct FakeOcr {
+        calls: Mutex<Vec<usize>>,
+        fail: bool,
+    }
+
+    impl PdfExtractor for FakeOcr {
+        fn cache_identity(&self) -> Option<String> {
+            Some("fake-ocr".to_string())
+        }
+
+        fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
+            let page_count = lopdf::Document::load(path)
+                .map(|document| document.get_pages().len())
+                .unwrap_or(1);
+            self.calls.lock().unwrap().push(page_count);
+            if self.fail {
+                return Err(IngestError::OcrUnavailable("fake ocr is down".to_string()));
+            }
+
+            Ok((1..=page_count as u32)
+                .map(|number| PageText {
+                    number,
+                    text: format!("Recognised subset page {number}"),
+                    method: ExtractionMethod::Ocr,
+                    confidence: Some(0.9),
+                    section: None,
+                    spans: Vec::new(),
+                    failure: None,
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn acceptance_rejects_short_and_garbage_pages() {
+        let acceptance = PageAcceptance::default();
+        assert!(!acceptance.accepts(""));
+        assert!(!acceptance.accepts("Page 3 of 12"));
+        assert!(!acceptance.accepts(
+            "\u{fffd}\u{fffd}\u{e001}\u{e002} \u{fffd}\u{fffd}\u{e003}\u{e004} abcdefghijklmnop"
+        ));
+        assert!(acceptance.accepts("5.2.3 Relief valve cracking pressure shall be 210 bar ± 5 %."));
+        assert!(PageAcceptance::any().accepts("Page 3 of 12"));
+    }
+
+    #[test]
+    fn default_chain_reads_only_the_text_layer() {
+        let chain = ExtractorChain::default();
+        assert_eq!(chain.steps().len(), 1);
+        assert_eq!(chain.steps()[0].extractor.name(), "lopdf");
+    }
+
+    #[test]
+    fn later_steps_only_see_rejected_pages() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("mixed.pdf");
+        write_pdf(&path, &["Hydraulic pump start-up procedure", "", "Page 3"]);
+
+        let ocr = std::sync::Arc::new(FakeOcr::default());
+        let mut chain =
+            ExtractorChain::new().then(LopdfExtractor::default(), PageAcceptance::default());
+        chain.push(ocr.clone(), PageAcceptance::any());
+
+        let pages = chain.extract(&path, &PasswordMap::new(), None)?;
+
+        assert_eq!(*ocr.calls.lock().unwrap(), vec![2]);
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (page.number, page.method))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, ExtractionMethod::TextLayer),
+                (2, ExtractionMethod::Ocr),
+                (3, ExtractionMethod::Ocr),
+            ]
+        );
+        assert!(pages[0].text.contains("Hydraulic pump"));
+        assert_eq!(pages[2].text, "Recognised subset page 2");
+        Ok(())
+    }
+
+    #[test]
+    fn failed_steps_keep_the_best_rejected_text() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("mixed.pdf");
+        write_pdf(&path, &["Hydraulic pump start-up procedure", "Page 2"]);
+
+        let chain = ExtractorChain::new()
+            .then(LopdfExtractor::default(), PageAcceptance::default())
+            .then(
+                FakeOcr {
+                    fail: true,
+                    ..FakeOcr::default()
+                },
+                PageAcceptance::any(),
+            );
+
+        let pages = chain.extract(&path, &PasswordMap::new(), None)?;
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].method, ExtractionMethod::TextLayer);
+        assert!(pages[1].text.contains("Page 2"));
+        assert_eq!(pages[0].failure, None);
+        assert!(pages[1]
+            .failure
+            .as_deref()
+            .is_some_and(|failure| failure.contains("fake ocr is down")));
+        Ok(())
+    }
+
+    #[test]
+    fn decrypted_pdfs_reach_steps_as_in_memory_subsets() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("supplier.pdf");
+        let mut document = build_pdf(&["Hydraulic pump start-up procedure", ""]);
+        encrypt_rc4(&mut document, "s3cret");
+        document.save(&path)?;
+        let mut passwords = PasswordMap::new();
+        passwords.insert("supplier.pdf", "s3cret")?;
+
+        let ocr = std::sync::Arc::new(FakeOcr::default());
+        let mut chain =
+            ExtractorChain::new().then(LopdfExtractor::default(), PageAcceptance::default());
+        chain.push(ocr.clone(), PageAcceptance::any());
+        let pages = chain.extract(&path, &passwords, None)?;
+
+        assert!(pages[0].text.contains("Hydraulic pump"));
+        assert_eq!(pages[1].method, ExtractionMethod::Ocr);
+        assert_eq!(*ocr.calls.lock().unwrap(), vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn unparseable_pdfs_go_whole_to_later_steps() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("corrupt.pdf");
+        std::fs::write(&path, b"%PDF-1.4\n%broken")?;
+
+        let text_only =
+            ExtractorChain::new().then(LopdfExtractor::default(), PageAcceptance::default());
+        let error = text_only
+            .extract(&path, &PasswordMap::new(), None)
+            .map(|_| ())
+            .expect_err("no step can read a corrupt pdf");
+        assert_eq!(error.category(), FailureCategory::Corrupt);
+
+        let pages = text_only
+            .then(FakeOcr::default(), PageAcceptance::any())
+            .extract(&path, &PasswordMap::new(), None)?;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].method, ExtractionMethod::Ocr);
+        Ok(())
+    }
+
+    #[test]
+    fn cached_ocr_pages_skip_the_backend() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("mixed.pdf");
+        write_pdf(&path, &["Hydraulic pump start-up procedure", ""]);
+        let cache = OcrCache::new(dir.path().join("ocr-cache"));
+
+        let ocr = std::sync::Arc::new(FakeOcr::default());
+        let mut chain =
+            ExtractorChain::new().then(LopdfExtractor::default(), PageAcceptance::default());
+        chain.push(ocr.clone(), PageAcceptance::any());
+
+        let first = chain.extract(&path, &PasswordMap::new(), Some(&cache))?;
+        let second = chain.extract(&path, &PasswordMap::new(), Some(&cache))?;
+
+        assert_eq!(ocr.calls.lock().unwrap().len(), 1);
+        assert_eq!(first[1].text, second[1].text);
+        assert_eq!(second[1].method, ExtractionMethod::Ocr);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().writes, 1);
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use crate::error::IngestError;
-use crate::models::{ChunkKind, DocumentFingerprint, IngestionOptions, PdfChunk};
+use crate::extractor::{ExtractionMethod, PageText};
+use crate::models::{
+    BoundingBox, ChunkKind, DocumentFingerprint, IngestionOptions, PageSpan, PdfChunk,
+};
 use regex::Regex;
 use sha2::{Digest, Sha256};
+use std::ops::Range;
 
 #[derive(Debug, Clone, Copy)]
 pub struct ChunkingConfig {
@@ -28,85 +32,164 @@ pub fn normalize_whitespace(text: &str) -> String {
 }
 
 pub fn chunk_by_paragraph(normalized: &str, config: ChunkingConfig) -> Vec<String> {
-    let raw_paragraphs = normalized
-        .split("\n\n")
-        .map(|paragraph| paragraph.trim().replace('\t', " "))
-        .filter(|paragraph| !paragraph.trim().is_empty())
-        .collect::<Vec<_>>();
-
-    let mut chunks = Vec::new();
-    let mut current = String::new();
+    let chars = normalized.chars().collect::<Vec<_>>();
+    chunk_ranges(&chars, config)
+        .into_iter()
+        .map(|range| chars[range].iter().collect::<String>().replace('\t', " "))
+        .collect()
+}
 
-    for paragraph in raw_paragraphs {
-        if current.is_empty() {
-            current.push_str(&paragraph);
+/// Char ranges of `chars` that become chunks: paragraphs (split on blank
+/// lines) are packed up to `max_chars`, and longer ones are cut into
+/// overlapping windows.
+fn chunk_ranges(chars: &[char], config: ChunkingConfig) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+    while index <= chars.len() {
+        let boundary =
+            index == chars.len() || (chars[index] == '\n' && chars.get(index + 1) == Some(&'\n'));
+        if !boundary {
+            index += 1;
             continue;
         }
 
-        if current.len() + paragraph.len() + 2 <= config.max_chars {
-            current.push_str("\n\n");
-            current.push_str(&paragraph);
-        } else {
-            if current.len() >= config.min_chars {
-                chunks.push(current.clone());
-            }
-            current.clear();
-            current.push_str(&paragraph);
+        let mut paragraph = start..index;
+        while paragraph.start < paragraph.end && chars[paragraph.start].is_whitespace() {
+            paragraph.start += 1;
         }
+        while paragraph.end > paragraph.start && chars[paragraph.end - 1].is_whitespace() {
+            paragraph.end -= 1;
+        }
+        if !paragraph.is_empty() {
+            paragraphs.push(paragraph);
+        }
+        index += 2;
+        start = index;
     }
 
-    if current.len() >= config.min_chars {
-        chunks.push(current);
+    let mut chunks = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    for paragraph in paragraphs {
+        current = match current {
+            None => Some(paragraph),
+            Some(joined) if paragraph.end - joined.start <= config.max_chars => {
+                Some(joined.start..paragraph.end)
+            }
+            Some(joined) => {
+                if joined.len() >= config.min_chars {
+                    chunks.push(joined);
+                }
+                Some(paragraph)
+            }
+        };
+    }
+    if let Some(joined) = current.filter(|joined| joined.len() >= config.min_chars) {
+        chunks.push(joined);
     }
 
-    if chunks.is_empty() && !normalized.trim().is_empty() {
-        chunks.push(normalized.trim().to_string());
+    if chunks.is_empty() {
+        let first = chars.iter().position(|ch| !ch.is_whitespace());
+        let last = chars.iter().rposition(|ch| !ch.is_whitespace());
+        if let (Some(first), Some(last)) = (first, last) {
+            chunks.push(first..last + 1);
+        }
     }
 
-    let mut with_overlap = Vec::new();
+    let step = config.max_chars.saturating_sub(config.overlap_chars).max(1);
+    let mut windows = Vec::new();
     for chunk in chunks {
         if chunk.len() <= config.max_chars {
-            with_overlap.push(chunk);
+            windows.push(chunk);
             continue;
         }
 
-        let chars: Vec<char> = chunk.chars().collect();
-        let mut start = 0;
-        while start < chars.len() {
-            let end = (start + config.max_chars).min(chars.len());
-            let piece: String = chars[start..end].iter().collect();
-            with_overlap.push(piece);
-            if end == chars.len() {
+        let mut start = chunk.start;
+        loop {
+            let end = (start + config.max_chars).min(chunk.end);
+            windows.push(start..end);
+            if end == chunk.end {
                 break;
             }
-            start = start.saturating_add(config.max_chars.saturating_sub(config.overlap_chars));
+            start += step;
+        }
+    }
+
+    windows
+}
+
+/// `normalize_whitespace` that also returns, for every output char, the char
+/// offset it came from in `text`.
+fn normalize_with_offsets(text: &str) -> (String, Vec<usize>) {
+    let mut normalized = String::new();
+    let mut offsets = Vec::new();
+    let mut pending_space = None;
+
+    for (offset, ch) in text.chars().enumerate() {
+        if ch.is_whitespace() {
+            if !normalized.is_empty() && pending_space.is_none() {
+                pending_space = Some(offset);
+            }
+            continue;
+        }
+        if let Some(space) = pending_space.take() {
+            normalized.push(' ');
+            offsets.push(space);
         }
+        normalized.push(ch);
+        offsets.push(offset);
     }
 
-    with_overlap
+    (normalized, offsets)
 }
 
+/// Chunks a run of consecutive pages that share a section, so a paragraph
+/// that continues over a page break can land in one chunk. Every chunk
+/// records which characters of which pages it covers.
 pub fn build_chunks(
     document: &DocumentFingerprint,
-    page: u32,
+    pages: &[PageText],
     section_context: &str,
     clause_id: Option<String>,
-    page_text: &str,
     options: &IngestionOptions,
     global_index: u64,
 ) -> Result<(Vec<PdfChunk>, u64), IngestError> {
     let config = ChunkingConfig::from(options.clone());
-    let normalized = normalize_whitespace(page_text);
     let section_heading_re = Regex::new(options.section_heading_regex)?;
     let clause_re = Regex::new(options.clause_regex)?;
 
+    let mut text = Vec::<char>::new();
+    let mut origins = Vec::<Option<(usize, usize)>>::new();
+    for (page_index, page) in pages.iter().enumerate() {
+        let (normalized, offsets) = normalize_with_offsets(&page.text);
+        if normalized.is_empty() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.extend(['\n', '\n']);
+            origins.extend([None, None]);
+        }
+        text.extend(normalized.chars());
+        origins.extend(offsets.into_iter().map(|offset| Some((page_index, offset))));
+    }
+
     let mut chunks = Vec::new();
     let mut cursor = global_index;
 
-    for raw_chunk in chunk_by_paragraph(&normalized, config) {
+    for range in chunk_ranges(&text, config) {
+        let raw_chunk = text[range.clone()].iter().collect::<String>();
         if raw_chunk.trim().len() < config.min_chars {
             continue;
         }
+        let page_spans = page_spans(pages, &origins[range]);
+        let (Some(first_span), Some(last_span)) = (page_spans.first(), page_spans.last()) else {
+            continue;
+        };
+        let (page_start, page_end) = (first_span.page, last_span.page);
+        let covered = pages
+            .iter()
+            .filter(|page| page_spans.iter().any(|span| span.page == page.number))
+            .collect::<Vec<_>>();
 
         let first_line = raw_chunk
             .lines()
@@ -124,7 +207,7 @@ pub fn build_chunks(
             section_context.to_string()
         };
 
-        let chunk_id = make_chunk_id(&document.document_id, page, cursor, &raw_chunk);
+        let chunk_id = make_chunk_id(&document.document_id, page_start, cursor, &raw_chunk);
 
         chunks.push(PdfChunk {
             chunk_id,
@@ -135,8 +218,8 @@ pub fn build_chunks(
             standard: document.standard.clone(),
             section_path: final_section.clone(),
             clause_id: clause_match.or_else(|| clause_id.clone()),
-            page_start: page,
-            page_end: page,
+            page_start,
+            page_end,
             chunk_index: cursor,
             text_raw: raw_chunk.clone(),
             text_normalized: normalize_whitespace(&raw_chunk),
@@ -145,9 +228,21 @@ pub fn build_chunks(
             } else {
                 ChunkKind::Paragraph
             },
-            ocr_confidence: None,
+            extraction_method: if covered
+                .iter()
+                .any(|page| page.method == ExtractionMethod::Ocr)
+            {
+                ExtractionMethod::Ocr
+            } else {
+                ExtractionMethod::TextLayer
+            },
+            ocr_confidence: covered
+                .iter()
+                .filter_map(|page| page.confidence)
+                .reduce(f32::min),
             references: Vec::new(),
             units: extract_unit_tokens(&raw_chunk),
+            page_spans,
         });
 
         cursor = cursor.saturating_add(1);
@@ -156,6 +251,36 @@ pub fn build_chunks(
     Ok((chunks, cursor))
 }
 
+fn page_spans(pages: &[PageText], origins: &[Option<(usize, usize)>]) -> Vec<PageSpan> {
+    let mut spans = Vec::<(usize, PageSpan)>::new();
+    for (page_index, offset) in origins.iter().flatten().copied() {
+        match spans.last_mut() {
+            Some((index, span)) if *index == page_index => span.char_end = offset + 1,
+            _ => spans.push((
+                page_index,
+                PageSpan {
+                    page: pages[page_index].number,
+                    char_start: offset,
+                    char_end: offset + 1,
+                    bbox: None,
+                },
+            )),
+        }
+    }
+    spans
+        .into_iter()
+        .map(|(page_index, span)| PageSpan {
+            bbox: pages[page_index]
+                .spans
+                .iter()
+                .filter(|text| text.char_start < span.char_end && text.char_end > span.char_start)
+                .map(|text| text.bbox)
+                .reduce(BoundingBox::union),
+            ..span
+        })
+        .collect()
+}
+
 fn make_chunk_id(document_id: &str, page: u32, index: u64, text: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(document_id.as_bytes());
@@ -201,6 +326,7 @@ mod tests {
             min_chunk_chars: 5,
             section_heading_regex: r"(?m)^Section",
             clause_regex: r"(?m)^Clause",
+            ..IngestionOptions::default()
         };
 
         let document = DocumentFingerprint {
@@ -213,8 +339,11 @@ mod tests {
             ingested_at: chrono::Utc::now(),
         };
 
-        let page_text = "Section 1\n\nSome long paragraph with numbers and terms.";
-        let result = build_chunks(&document, 1, "Section 1", None, page_text, &options, 0)
+        let page = text_page(
+            1,
+            "Section 1\n\nSome long paragraph with numbers and terms.",
+        );
+        let result = build_chunks(&document, &[page], "Section 1", None, &options, 0)
             .unwrap()
             .0;
 
@@ -224,4 +353,140 @@ mod tests {
             result[0].kind == super::ChunkKind::Heading || result[0].kind == ChunkKind::Paragraph
         );
     }
+
+    fn text_page(number: u32, text: &str) -> PageText {
+        PageText {
+            number,
+            text: text.to_string(),
+            method: ExtractionMethod::TextLayer,
+            confidence: None,
+            section: None,
+            spans: Vec::new(),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn chunks_spanning_a_page_break_record_offsets_on_both_pages() {
+        let options = IngestionOptions {
+            chunk_max_chars: 200,
+            min_chunk_chars: 5,
+            ..IngestionOptions::default()
+        };
+        let document = DocumentFingerprint {
+            document_id: "doc-1".to_string(),
+            document_title: "pump.pdf".to_string(),
+            source_path: "/tmp/pump.pdf".to_string(),
+            version: None,
+            standard: None,
+            checksum: "checksum".to_string(),
+            ingested_at: chrono::Utc::now(),
+        };
+        let mut scanned = text_page(15, "the valve\n  seat before restart.");
+        scanned.method = ExtractionMethod::Ocr;
+        scanned.confidence = Some(0.7);
+        let pages = [
+            text_page(14, "Header\n\n5.2.3 Inspect   the relief valve and"),
+            scanned,
+        ];
+
+        let (chunks, next) =
+            build_chunks(&document, &pages, "unassigned", None, &options, 7).expect("chunks");
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(next, 8);
+        let chunk = &chunks[0];
+        assert_eq!((chunk.page_start, chunk.page_end), (14, 15));
+        assert_eq!(chunk.extraction_method, ExtractionMethod::Ocr);
+        assert_eq!(chunk.ocr_confidence, Some(0.7));
+        assert_eq!(chunk.page_spans.len(), 2);
+        assert_eq!(chunk.page_spans[0].page, 14);
+        assert_eq!(chunk.page_spans[0].char_start, 0);
+        assert_eq!(chunk.page_spans[0].char_end, pages[0].text.chars().count());
+        assert_eq!(chunk.page_spans[1].page, 15);
+        let tail = &pages[1].text.chars().collect::<Vec<_>>()
+            [chunk.page_spans[1].char_start..chunk.page_spans[1].char_end];
+        assert_eq!(tail.iter().collect::<String>(), pages[1].text);
+    }
+
+    #[test]
+    fn page_spans_take_the_bounding_box_of_covered_layout_spans() {
+        let options = IngestionOptions {
+            chunk_max_chars: 28,
+            chunk_overlap_chars: 0,
+            min_chunk_chars: 5,
+            ..IngestionOptions::default()
+        };
+        let document = DocumentFingerprint {
+            document_id: "doc-1".to_string(),
+            document_title: "pump.pdf".to_string(),
+            source_path: "/tmp/pump.pdf".to_string(),
+            version: None,
+            standard: None,
+            checksum: "checksum".to_string(),
+            ingested_at: chrono::Utc::now(),
+        };
+        let span = |text: &str, x0: f32, y0: f32| crate::TextSpan {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x0,
+                y0,
+                x1: x0 + 100.0,
+                y1: y0 + 10.0,
+            },
+            font_size: 10.0,
+            char_start: 0,
+            char_end: 0,
+        };
+        let (text, spans) = crate::layout_text(vec![
+            span("Relief valve set", 72.0, 700.0),
+            span("to 210 bar.", 72.0, 688.0),
+            span("Check the seat monthly.", 72.0, 600.0),
+        ]);
+        let page = PageText {
+            spans,
+            failure: None,
+            ..text_page(3, &text)
+        };
+
+        let (chunks, _) =
+            build_chunks(&document, &[page], "unassigned", None, &options, 0).expect("chunks");
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].page_spans[0].bbox,
+            Some(BoundingBox {
+                x0: 72.0,
+                y0: 688.0,
+                x1: 172.0,
+                y1: 710.0,
+            })
+        );
+        assert_eq!(
+            chunks[1].page_spans[0].bbox.map(|bbox| (bbox.y0, bbox.y1)),
+            Some((600.0, 610.0))
+        );
+    }
+
+    #[test]
+    fn citations_name_pages_and_clause() {
+        let citation = crate::Citation {
+            title: "pump.pdf".to_string(),
+            page_start: 14,
+            page_end: 15,
+            section: crate::models::citation_section(
+                Some("5.2.3 Relief valves"),
+                "5.2.3 Relief valves",
+            ),
+            page_spans: Vec::new(),
+        };
+        assert_eq!(citation.to_string(), "pump.pdf p.14\u{2013}15, \u{a7}5.2.3");
+
+        let single = crate::Citation {
+            page_end: 14,
+            section: crate::models::citation_section(None, "Pump > Start-up"),
+            ..citation
+        };
+        assert_eq!(single.to_string(), "pump.pdf p.14, Pump > Start-up");
+    }
 }
@@ -0,0 +1,266 @@
+use crate::error::IngestError;
+use ignore::gitignore::GitignoreBuilder;
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE_NAME: &str = ".docneedleignore";
+
+/// Which files under an ingest folder are looked at before any document
+/// source sees them.
+#[derive(Debug, Clone)]
+pub struct DiscoveryPolicy {
+    /// Gitignore-style globs relative to the folder; when non-empty, only
+    /// matching files are discovered.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_file_bytes: Option<u64>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    /// Walk dot-files and dot-directories; on by default, as ingestion always has.
+    pub include_hidden: bool,
+    /// Honour `.docneedleignore` files anywhere in the folder tree.
+    pub use_ignore_files: bool,
+}
+
+impl Default for DiscoveryPolicy {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_file_bytes: None,
+            max_depth: None,
+            follow_symlinks: false,
+            include_hidden: true,
+            use_ignore_files: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnreadablePath {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveredFiles {
+    pub files: Vec<PathBuf>,
+    /// Files above `max_file_bytes`, with their size in bytes.
+    pub oversized: Vec<(PathBuf, u64)>,
+    pub unreadable: Vec<UnreadablePath>,
+}
+
+pub fn discover_files(
+    folder: &Path,
+    policy: &DiscoveryPolicy,
+    wanted: impl Fn(&Path) -> bool,
+) -> Result<DiscoveredFiles, IngestError> {
+    let invalid_glob = |error: ignore::Error| {
+        IngestError::InvalidArgument(format!("invalid discovery glob: {error}"))
+    };
+    // Includes are matched separately so they never override ignore files;
+    // an override whitelist would take precedence over `.docneedleignore`.
+    let mut includes = GitignoreBuilder::new(folder);
+    for pattern in &policy.include {
+        includes.add_line(None, pattern).map_err(invalid_glob)?;
+    }
+    let includes = includes.build().map_err(invalid_glob)?;
+    let mut overrides = OverrideBuilder::new(folder);
+    for pattern in &policy.exclude {
+        overrides
+            .add(&format!("!{pattern}"))
+            .map_err(invalid_glob)?;
+    }
+
+    let mut walker = WalkBuilder::new(folder);
+    walker
+        .standard_filters(false)
+        .hidden(!policy.include_hidden)
+        .max_depth(policy.max_depth)
+        .follow_links(policy.follow_symlinks)
+        .overrides(overrides.build().map_err(invalid_glob)?);
+    if policy.use_ignore_files {
+        walker.add_custom_ignore_filename(IGNORE_FILE_NAME);
+    }
+
+    let mut discovered = DiscoveredFiles::default();
+    for entry in walker.build() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                discovered.unreadable.push(UnreadablePath {
+                    path: error_path(&error).unwrap_or_else(|| folder.to_path_buf()),
+                    reason: error
+                        .io_error()
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| error.to_string()),
+                });
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) || !wanted(entry.path()) {
+            continue;
+        }
+        if !includes.is_empty()
+            && !includes
+                .matched_path_or_any_parents(entry.path(), false)
+                .is_ignore()
+        {
+            continue;
+        }
+
+        let path = entry.into_path();
+        if let Some(limit) = policy.max_file_bytes {
+            match path.metadata() {
+                Ok(metadata) if metadata.len() > limit => {
+                    discovered.oversized.push((path, metadata.len()));
+                    continue;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    discovered.unreadable.push(UnreadablePath {
+                        path,
+                        reason: error.to_string(),
+                    });
+                    continue;
+                }
+            }
+        }
+        discovered.files.push(path);
+    }
+
+    discovered.files.sort_unstable();
+    discovered.oversized.sort_unstable();
+    Ok(discovered)
+}
+
+fn error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::Loop { child, .. } => Some(child.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Partial(errors) => errors.iter().find_map(error_path),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover_files, DiscoveryPolicy, IGNORE_FILE_NAME};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn is_pdf(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "pdf")
+    }
+
+    fn relative(root: &Path, paths: &[std::path::PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .expect("under root")
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn globs_ignore_files_and_hidden_entries_filter_discovery(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        for file in [
+            "manuals/pump.pdf",
+            "manuals/drafts/pump-v0.pdf",
+            "manuals/scratch.pdf",
+            "vendor/valve.pdf",
+            ".cache/hidden.pdf",
+        ] {
+            fs::create_dir_all(root.join(file).parent().expect("parent"))?;
+            fs::write(root.join(file), b"%PDF-1.4")?;
+        }
+        fs::write(root.join("manuals").join(IGNORE_FILE_NAME), "scratch.pdf\n")?;
+
+        let policy = DiscoveryPolicy {
+            include: vec!["manuals/**".to_string()],
+            exclude: vec!["drafts/".to_string()],
+            ..DiscoveryPolicy::default()
+        };
+        let discovered = discover_files(root, &policy, is_pdf)?;
+        assert_eq!(relative(root, &discovered.files), vec!["manuals/pump.pdf"]);
+
+        let everything = DiscoveryPolicy {
+            use_ignore_files: false,
+            ..DiscoveryPolicy::default()
+        };
+        let discovered = discover_files(root, &everything, is_pdf)?;
+        assert_eq!(discovered.files.len(), 5);
+
+        let visible = DiscoveryPolicy {
+            include_hidden: false,
+            ..everything
+        };
+        let discovered = discover_files(root, &visible, is_pdf)?;
+        assert_eq!(discovered.files.len(), 4);
+        assert!(!discovered
+            .files
+            .iter()
+            .any(|path| path.ends_with(".cache/hidden.pdf")));
+        Ok(())
+    }
+
+    #[test]
+    fn depth_and_size_limits_are_applied() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b"))?;
+        fs::write(root.join("top.pdf"), vec![b'x'; 10])?;
+        fs::write(root.join("a/large.pdf"), vec![b'x'; 100])?;
+        fs::write(root.join("a/b/deep.pdf"), vec![b'x'; 10])?;
+
+        let policy = DiscoveryPolicy {
+            max_depth: Some(2),
+            max_file_bytes: Some(50),
+            ..DiscoveryPolicy::default()
+        };
+        let discovered = discover_files(root, &policy, is_pdf)?;
+
+        assert_eq!(relative(root, &discovered.files), vec!["top.pdf"]);
+        assert_eq!(discovered.oversized.len(), 1);
+        assert_eq!(discovered.oversized[0].1, 100);
+        assert!(discovered.unreadable.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_only_followed_when_enabled() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let outside = tempdir()?;
+        fs::write(outside.path().join("linked.pdf"), b"%PDF-1.4")?;
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("shared"))?;
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop"))?;
+
+        let discovered = discover_files(dir.path(), &DiscoveryPolicy::default(), is_pdf)?;
+        assert!(discovered.files.is_empty());
+
+        let follow = DiscoveryPolicy {
+            follow_symlinks: true,
+            ..DiscoveryPolicy::default()
+        };
+        let discovered = discover_files(dir.path(), &follow, is_pdf)?;
+        assert_eq!(discovered.files.len(), 1);
+        assert!(discovered
+            .unreadable
+            .iter()
+            .any(|issue| issue.path.ends_with("loop")));
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::SearchCandidate;
+use std::collections::HashMap;
+
+/// How many candidates per final slot are retrieved when hits are collapsed
+/// or diversified, so that dropping near-duplicates still fills `top_k`.
+pub const DIVERSITY_POOL_FACTOR: usize = 4;
+
+/// Keeps the best-ranked hit of each document, in rank order, and counts the
+/// dropped ones in its `collapsed` field.
+pub fn collapse_by_document(hits: Vec<SearchCandidate>) -> Vec<SearchCandidate> {
+    let mut kept = Vec::<SearchCandidate>::new();
+    let mut positions = HashMap::<String, usize>::new();
+    for hit in hits {
+        match positions.get(&hit.document_id) {
+            Some(&position) => kept[position].collapsed += 1,
+            None => {
+                positions.insert(hit.document_id.clone(), kept.len());
+                kept.push(hit);
+            }
+        }
+    }
+    kept
+}
+
+/// Maximal marginal relevance: repeatedly picks the hit maximising
+/// `lambda * relevance - (1 - lambda) * max similarity to the picks so far`,
+/// where relevance is the min-max normalised score and similarity the cosine
+/// of the chunk embeddings. Returns at most `limit` hits in pick order.
+pub fn mmr(
+    hits: Vec<SearchCandidate>,
+    embeddings: &[Vec<f32>],
+    lambda: f64,
+    limit: usize,
+) -> Vec<SearchCandidate> {
+    let (min, max) = hits
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), hit| {
+            (min.min(hit.score), max.max(hit.score))
+        });
+    let relevance = hits
+        .iter()
+        .map(|hit| {
+            if max > min {
+                (hit.score - min) / (max - min)
+            } else {
+                1.0
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Highest similarity of each hit to the picks so far, updated against
+    // each new pick only.
+    let mut redundancy = vec![0.0; hits.len()];
+    let mut remaining = (0..hits.len()).collect::<Vec<_>>();
+    let mut picked = Vec::<usize>::new();
+    while picked.len() < limit && !remaining.is_empty() {
+        let (slot, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(slot, &candidate)| {
+                (
+                    slot,
+                    lambda * relevance[candidate] - (1.0 - lambda) * redundancy[candidate],
+                )
+            })
+            // `max_by` keeps the last of equal values; reversing keeps the best-ranked one.
+            .rev()
+            .max_by(|left, right| left.1.total_cmp(&right.1))
+            .expect("remaining is not empty");
+        let chosen = remaining.remove(slot);
+        for &candidate in &remaining {
+            let similarity = cosine(&embeddings[candidate], &embeddings[chosen]);
+            redundancy[candidate] = redundancy[candidate].max(similarity);
+        }
+        picked.push(chosen);
+    }
+
+    let mut hits = hits.into_iter().map(Some).collect::<Vec<_>>();
+    picked
+        .into_iter()
+        .filter_map(|index| hits[index].take())
+        .collect()
+}
+
+fn cosine(left: &[f32], right: &[f32]) -> f64 {
+    let dot = left
+        .iter()
+        .zip(right)
+        .map(|(left, right)| f64::from(*left) * f64::from(*right))
+        .sum::<f64>();
+    let norm = |vector: &[f32]| {
+        vector
+            .iter()
+            .map(|value| f64::from(*value).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    };
+    let norms = norm(left) * norm(right);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchMode;
+
+    fn hit(chunk_id: &str, document_id: &str, score: f64) -> SearchCandidate {
+        SearchCandidate {
+            chunk_id: chunk_id.to_string(),
+            document_id: document_id.to_string(),
+            source_path: format!("/tmp/{document_id}.pdf"),
+            score,
+            source: "opensearch".to_string(),
+            chunk: None,
+            text: None,
+            mode: SearchMode::Keyword,
+            ocr_confidence: None,
+            citation: None,
+            clause_id: None,
+            explanation: None,
+            collapsed: 0,
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            highlights: Vec::new(),
+        }
+    }
+
+    fn ids(hits: &[SearchCandidate]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.chunk_id.as_str()).collect()
+    }
+
+    #[test]
+    fn collapsing_keeps_the_best_chunk_per_document_and_counts_the_rest() {
+        let collapsed = collapse_by_document(vec![
+            hit("pump-1", "pump", 0.9),
+            hit("pump-2", "pump", 0.8),
+            hit("valve-1", "valve", 0.7),
+            hit("pump-3", "pump", 0.6),
+        ]);
+
+        assert_eq!(ids(&collapsed), vec!["pump-1", "valve-1"]);
+        assert_eq!(collapsed[0].collapsed, 2);
+        assert_eq!(collapsed[1].collapsed, 0);
+    }
+
+    #[test]
+    fn mmr_trades_relevance_for_novelty() {
+        let hits = vec![
+            hit("a", "pump", 1.0),
+            hit("a-again", "pump", 0.95),
+            hit("b", "valve", 0.5),
+        ];
+        let embeddings = vec![vec![1.0, 0.0], vec![0.99, 0.1], vec![0.0, 1.0]];
+
+        let relevant = mmr(hits.clone(), &embeddings, 1.0, 2);
+        assert_eq!(ids(&relevant), vec!["a", "a-again"]);
+
+        let diverse = mmr(hits, &embeddings, 0.5, 3);
+        assert_eq!(ids(&diverse), vec!["a", "b", "a-again"]);
+    }
+
+    #[test]
+    fn mmr_picks_as_if_comparing_against_every_earlier_pick() {
+        // Deterministic pseudo-random scores and embeddings.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 1000) as f32 / 1000.0
+        };
+        let hits = (0..300)
+            .map(|index| hit(&format!("chunk-{index}"), "doc", f64::from(next())))
+            .collect::<Vec<_>>();
+        let embeddings = (0..hits.len())
+            .map(|_| (0..8).map(|_| next() - 0.5).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let (min, max) = hits
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), hit| {
+                (min.min(hit.score), max.max(hit.score))
+            });
+
+        let mut expected = Vec::<usize>::new();
+        let mut remaining = (0..hits.len()).collect::<Vec<_>>();
+        while expected.len() < 100 {
+            let (slot, _) = remaining
+                .iter()
+                .enumerate()
+                .map(|(slot, &candidate)| {
+                    let redundancy = expected
+                        .iter()
+                        .map(|&chosen| cosine(&embeddings[candidate], &embeddings[chosen]))
+                        .fold(0.0, f64::max);
+                    let relevance = (hits[candidate].score - min) / (max - min);
+                    (slot, 0.7 * relevance - 0.3 * redundancy)
+                })
+                .rev()
+                .max_by(|left, right| left.1.total_cmp(&right.1))
+                .expect("remaining is not empty");
+            expected.push(remaining.remove(slot));
+        }
+
+        let picked = mmr(hits.clone(), &embeddings, 0.7, 100);
+        assert_eq!(
+            ids(&picked),
+            expected
+                .iter()
+                .map(|&index| hits[index].chunk_id.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
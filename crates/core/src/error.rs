@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -5,8 +6,26 @@ pub enum IngestError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("pdf parse error: {0}")]
-    PdfParse(String),
+    #[error("pdf is encrypted: {0}")]
+    Encrypted(String),
+
+    #[error("pdf is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("pdf has no text layer: {0}")]
+    NoTextLayer(String),
+
+    #[error("unsupported pdf: {0}")]
+    Unsupported(String),
+
+    #[error("OCR unavailable: {0}")]
+    OcrUnavailable(String),
+
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    #[error("file too large: {0}")]
+    TooLarge(String),
 
     #[error("regex error: {0}")]
     RegexError(#[from] regex::Error),
@@ -25,6 +44,48 @@ pub enum IngestError {
 
     #[error("multimodal OCR failed: {0}")]
     OcrFailed(String),
+
+    #[error("serialize error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    Encrypted,
+    Corrupt,
+    NoTextLayer,
+    Unsupported,
+    OcrUnavailable,
+    OcrFailed,
+    Timeout,
+    TooLarge,
+    Io,
+    Http,
+    InvalidInput,
+    /// Malformed JSON, e.g. an unexpected OCR response; retrying won't help.
+    InvalidData,
+}
+
+impl IngestError {
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            IngestError::Io(_) | IngestError::MissingFileName(_) => FailureCategory::Io,
+            IngestError::Serialization(_) => FailureCategory::InvalidData,
+            IngestError::Encrypted(_) => FailureCategory::Encrypted,
+            IngestError::Corrupt(_) => FailureCategory::Corrupt,
+            IngestError::NoTextLayer(_) => FailureCategory::NoTextLayer,
+            IngestError::Unsupported(_) => FailureCategory::Unsupported,
+            IngestError::OcrUnavailable(_) => FailureCategory::OcrUnavailable,
+            IngestError::OcrFailed(_) => FailureCategory::OcrFailed,
+            IngestError::Timeout(_) => FailureCategory::Timeout,
+            IngestError::TooLarge(_) => FailureCategory::TooLarge,
+            IngestError::Http(_) => FailureCategory::Http,
+            IngestError::RegexError(_)
+            | IngestError::InvalidChunkConfig(_)
+            | IngestError::InvalidArgument(_) => FailureCategory::InvalidInput,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -44,8 +105,36 @@ pub enum SearchError {
     #[error("search request failed: {0}")]
     Request(String),
 
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("search timed out: {0}")]
+    Timeout(String),
+
     #[error("store not available yet: {0}")]
     NotReady(String),
+
+    #[error("not supported by this store: {0}")]
+    Unsupported(String),
 }
 
 pub type Result<T, E = IngestError> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::{FailureCategory, IngestError};
+
+    #[test]
+    fn malformed_json_is_invalid_data_rather_than_io() {
+        let malformed =
+            serde_json::from_str::<serde_json::Value>("{not json").expect_err("malformed json");
+        assert_eq!(
+            IngestError::from(malformed).category(),
+            FailureCategory::InvalidData
+        );
+        assert_eq!(
+            IngestError::Io(std::io::Error::other("disk")).category(),
+            FailureCategory::Io
+        );
+    }
+}
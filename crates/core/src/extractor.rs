@@ -1,258 +1,442 @@
 use crate::error::IngestError;
-use base64::{engine::general_purpose::STANDARD, Engine};
-use lopdf::Document;
-use reqwest::blocking::Client;
+use crate::layout::TextSpan;
+use crate::models::IngestionOptions;
+use crate::passwords::PasswordMap;
+use lopdf::encryption::DecryptionError;
+use lopdf::{Document, ObjectId};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionMethod {
+    #[default]
+    TextLayer,
+    Ocr,
+}
 
 #[derive(Debug, Clone)]
 pub struct PageText {
     pub number: u32,
     pub text: String,
+    pub method: ExtractionMethod,
+    pub confidence: Option<f32>,
+    pub section: Option<String>,
+    /// Positioned spans in reading order; empty unless the extractor reads layout.
+    pub spans: Vec<TextSpan>,
+    /// Why an extractor that should have read this page failed on it.
+    pub failure: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct LlmOcrRequest {
-    pdf_base64: String,
-    source_path: String,
-}
+pub trait PdfExtractor {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
 
-#[derive(Debug, Clone, Deserialize)]
-struct LlmOcrResponse {
-    pages: Option<Vec<LlmOcrPage>>,
-    text: Option<String>,
-}
+    /// Stable identity of the backend and its settings; extractors that return
+    /// one have their pages stored in the OCR cache.
+    fn cache_identity(&self) -> Option<String> {
+        None
+    }
 
-#[derive(Debug, Clone, Deserialize)]
-struct LlmOcrPage {
-    #[serde(default)]
-    page: Option<u32>,
-    #[serde(default)]
-    text: Option<String>,
-}
+    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError>;
 
-#[derive(Debug, Clone)]
-pub struct OcrEndpointConfig {
-    pub endpoint: String,
-    pub api_key: Option<String>,
+    /// Extracts a PDF holding only `page_numbers` of `source`; returned pages
+    /// carry the original page numbers.
+    fn extract_page_subset(
+        &self,
+        pdf: &[u8],
+        source: &Path,
+        page_numbers: &[u32],
+    ) -> Result<Vec<PageText>, IngestError> {
+        let workdir = ScratchDir::create()?;
+        let file_name = source
+            .file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("subset.pdf"));
+        let path = workdir.path().join(file_name);
+        fs::write(&path, pdf)?;
+
+        let pages = self.extract_pages(&path)?;
+        Ok(renumber_subset_pages(pages, page_numbers))
+    }
 }
 
-pub trait PdfExtractor {
-    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError>;
+#[derive(Debug, Clone, Default)]
+pub struct LopdfExtractor {
+    passwords: PasswordMap,
 }
 
-#[derive(Default)]
-pub struct LopdfExtractor;
+impl LopdfExtractor {
+    pub fn with_passwords(passwords: PasswordMap) -> Self {
+        Self { passwords }
+    }
+}
 
 impl PdfExtractor for LopdfExtractor {
-    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
-        let document =
-            Document::load(path).map_err(|error| IngestError::PdfParse(error.to_string()))?;
-
-        let mut pages = Vec::new();
-        for (page_no, _page_id) in document.get_pages() {
-            let text = document
-                .extract_text(&[page_no])
-                .map_err(|error| IngestError::PdfParse(error.to_string()))?;
-
-            if !text.trim().is_empty() {
-                pages.push(PageText {
-                    number: page_no,
-                    text,
-                });
-            }
-        }
+    fn name(&self) -> &str {
+        "lopdf"
+    }
 
-        if pages.is_empty() {
-            return Err(IngestError::PdfParse(format!(
-                "pdf had no readable page text: {}",
-                path.display()
-            )));
-        }
+    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
+        read_text_layer(path, &self.passwords, |document, page_no, _| {
+            Ok((document.extract_text(&[page_no])?, Vec::new()))
+        })
+    }
 
-        Ok(pages)
+    fn extract_page_subset(
+        &self,
+        pdf: &[u8],
+        source: &Path,
+        page_numbers: &[u32],
+    ) -> Result<Vec<PageText>, IngestError> {
+        let pages = read_subset_text_layer(pdf, source, |document, page_no, _| {
+            Ok((document.extract_text(&[page_no])?, Vec::new()))
+        })?;
+        Ok(renumber_subset_pages(pages, page_numbers))
     }
 }
 
-pub fn extract_page_texts(path: &Path) -> Result<Vec<PageText>, IngestError> {
-    let extracted = LopdfExtractor.extract_pages(path);
-
-    match extracted {
-        Ok(pages) => Ok(pages),
-        Err(IngestError::PdfParse(parse_error)) => match extract_with_llm_ocr(path) {
-            Ok(Some(pages)) => Ok(pages),
-            Ok(None) => Err(IngestError::PdfParse(parse_error)),
-            Err(ocr_error) => Err(IngestError::PdfParse(format!(
-                "{parse_error}; multimodal OCR fallback failed: {ocr_error}"
-            ))),
-        },
-        Err(error) => Err(error),
+/// Reads the text layer of every page with `read_page`, failing with
+/// `NoTextLayer` when no page has any text.
+pub(crate) fn read_text_layer(
+    path: &Path,
+    passwords: &PasswordMap,
+    read_page: impl Fn(&Document, u32, ObjectId) -> Result<(String, Vec<TextSpan>), lopdf::Error>,
+) -> Result<Vec<PageText>, IngestError> {
+    let (document, _) = open_document(path, passwords)?;
+    let pages = text_layer_pages(&document, path, read_page)?;
+
+    if pages.iter().all(|page| page.text.trim().is_empty()) {
+        return Err(IngestError::NoTextLayer(format!(
+            "pdf had no readable page text: {}",
+            path.display()
+        )));
     }
+
+    Ok(pages)
 }
 
-fn parse_llm_ocr_config() -> Option<OcrEndpointConfig> {
-    let endpoint = std::env::var("LLM_OCR_ENDPOINT").ok()?;
-    let endpoint = endpoint.trim().to_string();
-    if endpoint.is_empty() {
-        return None;
+/// `read_text_layer` over a subset built by `subset_pdf`, read from memory so
+/// that decrypted pages never touch the disk.
+pub(crate) fn read_subset_text_layer(
+    pdf: &[u8],
+    source: &Path,
+    read_page: impl Fn(&Document, u32, ObjectId) -> Result<(String, Vec<TextSpan>), lopdf::Error>,
+) -> Result<Vec<PageText>, IngestError> {
+    let document = Document::load_mem(pdf).map_err(|error| classify_lopdf_error(error, source))?;
+    let pages = text_layer_pages(&document, source, read_page)?;
+
+    if pages.iter().all(|page| page.text.trim().is_empty()) {
+        return Err(IngestError::NoTextLayer(format!(
+            "pdf had no readable page text: {}",
+            source.display()
+        )));
     }
 
-    let api_key = std::env::var("LLM_OCR_API_KEY").ok().and_then(|value| {
-        let key = value.trim().to_string();
-        if key.is_empty() {
-            None
-        } else {
-            Some(key)
-        }
-    });
-
-    Some(OcrEndpointConfig { endpoint, api_key })
+    Ok(pages)
 }
 
-fn extract_with_llm_ocr(path: &Path) -> Result<Option<Vec<PageText>>, IngestError> {
-    tokio::task::block_in_place(|| extract_with_llm_ocr_blocking(path))
-}
+/// Loads `path`, decrypting it when needed. The flag reports whether the
+/// document on disk was encrypted.
+pub(crate) fn open_document(
+    path: &Path,
+    passwords: &PasswordMap,
+) -> Result<(Document, bool), IngestError> {
+    let mut document = Document::load(path).map_err(|error| classify_lopdf_error(error, path))?;
 
-fn extract_with_llm_ocr_blocking(path: &Path) -> Result<Option<Vec<PageText>>, IngestError> {
-    let cfg = match parse_llm_ocr_config() {
-        Some(cfg) => cfg,
-        None => return Ok(None),
-    };
+    if !document.is_encrypted() {
+        return Ok((document, false));
+    }
 
-    let pdf = std::fs::read(path).map_err(IngestError::Io)?;
-    let payload = LlmOcrRequest {
-        pdf_base64: STANDARD.encode(pdf),
-        source_path: path.to_string_lossy().to_string(),
-    };
+    let configured = passwords.passwords_for(path);
+    for password in std::iter::once("").chain(configured.iter().copied()) {
+        match document.decrypt(password) {
+            Ok(()) => return Ok((document, true)),
+            Err(lopdf::Error::Decryption(DecryptionError::IncorrectPassword)) => continue,
+            Err(error) => return Err(classify_lopdf_error(error, path)),
+        }
+    }
 
-    let mut request = Client::new()
-        .post(&cfg.endpoint)
-        .header("content-type", "application/json")
-        .json(&payload);
+    Err(IngestError::Encrypted(if configured.is_empty() {
+        format!("no password configured for {}", path.display())
+    } else {
+        format!(
+            "none of {} configured password(s) opened {}",
+            configured.len(),
+            path.display()
+        )
+    }))
+}
+
+fn text_layer_pages(
+    document: &Document,
+    path: &Path,
+    read_page: impl Fn(&Document, u32, ObjectId) -> Result<(String, Vec<TextSpan>), lopdf::Error>,
+) -> Result<Vec<PageText>, IngestError> {
+    let mut pages = Vec::new();
+    let mut first_error = None;
+
+    for (page_no, page_id) in document.get_pages() {
+        let (text, spans) = match read_page(document, page_no, page_id) {
+            Ok(page) => page,
+            Err(error) => {
+                first_error.get_or_insert(error);
+                (String::new(), Vec::new())
+            }
+        };
 
-    if let Some(api_key) = cfg.api_key {
-        request = request.bearer_auth(api_key);
+        pages.push(PageText {
+            number: page_no,
+            text,
+            method: ExtractionMethod::TextLayer,
+            confidence: None,
+            section: None,
+            spans,
+            failure: None,
+        });
     }
 
-    let response = request.send()?;
+    match first_error {
+        Some(error) if pages.iter().all(|page| page.text.trim().is_empty()) => {
+            Err(classify_lopdf_error(error, path))
+        }
+        _ => Ok(pages),
+    }
+}
 
-    if !response.status().is_success() {
-        return Err(IngestError::OcrFailed(format!(
-            "multimodal OCR request to {} returned {}",
-            cfg.endpoint,
-            response.status()
-        )));
+fn classify_lopdf_error(error: lopdf::Error, path: &Path) -> IngestError {
+    let details = format!("{}: {error}", path.display());
+    match error {
+        lopdf::Error::IO(io_error) => IngestError::Io(io_error),
+        lopdf::Error::Decryption(DecryptionError::UnsupportedEncryption) => {
+            IngestError::Unsupported(details)
+        }
+        lopdf::Error::Decryption(_) => IngestError::Encrypted(details),
+        _ => IngestError::Corrupt(details),
     }
+}
 
-    let payload: LlmOcrResponse = response.json()?;
-    let pages = payload_to_pages(&payload, path)?;
+pub fn extract_page_texts(
+    path: &Path,
+    options: &IngestionOptions,
+) -> Result<Vec<PageText>, IngestError> {
+    options
+        .extractors
+        .extract(path, &options.passwords, options.ocr_cache.as_ref())
+}
 
-    if pages.is_empty() {
-        return Err(IngestError::OcrFailed(format!(
-            "multimodal OCR response has no readable text: {}",
-            path.display()
-        )));
-    }
+pub(crate) fn subset_pdf(document: &Document, keep: &[u32]) -> Result<Vec<u8>, IngestError> {
+    let mut subset = document.clone();
+    let dropped = subset
+        .get_pages()
+        .into_keys()
+        .filter(|page_no| !keep.contains(page_no))
+        .collect::<Vec<_>>();
+    subset.delete_pages(&dropped);
+    subset.prune_objects();
+
+    let mut pdf = Vec::new();
+    subset.save_to(&mut pdf)?;
+    Ok(pdf)
+}
 
-    Ok(Some(pages))
+pub(crate) fn renumber_subset_pages(pages: Vec<PageText>, page_numbers: &[u32]) -> Vec<PageText> {
+    pages
+        .into_iter()
+        .filter_map(|page| {
+            let index = usize::try_from(page.number).ok()?.checked_sub(1)?;
+            page_numbers
+                .get(index)
+                .map(|&number| PageText { number, ..page })
+        })
+        .collect()
 }
 
-fn payload_to_pages(payload: &LlmOcrResponse, path: &Path) -> Result<Vec<PageText>, IngestError> {
-    if let Some(listed) = &payload.pages {
-        let listed = listed
-            .iter()
-            .filter_map(|page| {
-                let text = page.text.as_ref().map(|value| value.trim().to_string());
-                text.and_then(|normalized| {
-                    if normalized.is_empty() {
-                        None
-                    } else {
-                        let page_number = page.page.unwrap_or(1);
-                        Some(PageText {
-                            number: page_number,
-                            text: normalized,
-                        })
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
-
-        if !listed.is_empty() {
-            return Ok(listed);
-        }
-    }
+/// Private (0700 on Unix) temporary directory, removed when dropped.
+pub(crate) struct ScratchDir {
+    dir: tempfile::TempDir,
+}
 
-    if let Some(raw_text) = &payload.text {
-        let pages = raw_text
-            .split('\u{000c}')
-            .enumerate()
-            .filter_map(|(index, chunk)| {
-                let normalized = chunk.trim().to_string();
-                if normalized.is_empty() {
-                    None
-                } else {
-                    Some(PageText {
-                        number: (index + 1) as u32,
-                        text: normalized,
-                    })
-                }
-            })
-            .collect::<Vec<_>>();
-
-        if !pages.is_empty() {
-            return Ok(pages);
+impl ScratchDir {
+    pub(crate) fn create() -> Result<Self, IngestError> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("docneedle-");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(fs::Permissions::from_mode(0o700));
         }
+        let dir = builder.tempdir()?;
+        Ok(Self { dir })
     }
 
-    Err(IngestError::OcrFailed(format!(
-        "multimodal OCR response was empty for {}",
-        path.display()
-    )))
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{payload_to_pages, LlmOcrPage, LlmOcrResponse};
+    use super::{
+        classify_lopdf_error, extract_page_texts, renumber_subset_pages, subset_pdf,
+        ExtractionMethod, LopdfExtractor, PageText, PdfExtractor, ScratchDir,
+    };
+    use crate::test_pdf::{build_pdf, encrypt_rc4, write_pdf};
+    use crate::{FailureCategory, IngestError, IngestionOptions, PasswordMap};
     use std::path::Path;
+    use tempfile::tempdir;
+
+    fn extraction_category(path: &Path) -> FailureCategory {
+        LopdfExtractor::default()
+            .extract_pages(path)
+            .map(|_| ())
+            .expect_err("extraction should fail")
+            .category()
+    }
 
     #[test]
-    fn ocr_payload_with_pages_converts_only_nonempty_text() {
-        let response = LlmOcrResponse {
-            pages: Some(vec![
-                LlmOcrPage {
-                    page: Some(2),
-                    text: Some("  ".to_string()),
-                },
-                LlmOcrPage {
-                    page: Some(3),
-                    text: Some("Page 3".to_string()),
-                },
-            ]),
-            text: None,
-        };
+    fn text_layer_pages_are_extracted_in_order() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("manual.pdf");
+        write_pdf(&path, &["Pump start procedure", "", "Relief valve"]);
+
+        let pages = LopdfExtractor::default().extract_pages(&path)?;
+
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].text.contains("Pump start procedure"));
+        assert!(pages[1].text.trim().is_empty());
+        assert_eq!(pages[2].number, 3);
+        assert_eq!(pages[2].method, ExtractionMethod::TextLayer);
+        Ok(())
+    }
 
-        let pages = payload_to_pages(&response, Path::new("x.pdf"))
-            .expect("multimodal response should be parsed");
+    #[test]
+    fn extraction_failures_are_classified() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+
+        let corrupt = dir.path().join("corrupt.pdf");
+        std::fs::write(&corrupt, b"%PDF-1.4\n%broken")?;
+        assert_eq!(extraction_category(&corrupt), FailureCategory::Corrupt);
+
+        let scanned = dir.path().join("scanned.pdf");
+        write_pdf(&scanned, &["", ""]);
+        assert_eq!(extraction_category(&scanned), FailureCategory::NoTextLayer);
+
+        let encrypted = dir.path().join("encrypted.pdf");
+        let mut document = build_pdf(&["Confidential"]);
+        encrypt_rc4(&mut document, "s3cret");
+        document.save(&encrypted)?;
+        assert_eq!(extraction_category(&encrypted), FailureCategory::Encrypted);
+
+        for malformed in [
+            lopdf::Error::ContentDecode,
+            lopdf::Error::Invalid("bad operator".to_string()),
+        ] {
+            assert_eq!(
+                classify_lopdf_error(malformed, &corrupt).category(),
+                FailureCategory::Corrupt
+            );
+        }
+        Ok(())
+    }
 
-        assert_eq!(pages.len(), 1);
-        assert_eq!(pages[0].number, 3);
-        assert_eq!(pages[0].text, "Page 3");
+    #[test]
+    fn encrypted_pdf_opens_with_matching_password() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("supplier-datasheet.pdf");
+        let mut document = build_pdf(&["Rated flow 40 l/min"]);
+        encrypt_rc4(&mut document, "s3cret");
+        document.save(&path)?;
+
+        let mut passwords = PasswordMap::new();
+        passwords.insert("other-*.pdf", "wrong")?;
+        let error = LopdfExtractor::with_passwords(passwords.clone())
+            .extract_pages(&path)
+            .map(|_| ())
+            .expect_err("no matching password");
+        assert_eq!(error.category(), FailureCategory::Encrypted);
+
+        passwords.insert("supplier-*.pdf", "not-it")?;
+        passwords.insert("supplier-*.pdf", "s3cret")?;
+        let pages = LopdfExtractor::with_passwords(passwords).extract_pages(&path)?;
+        assert!(pages[0].text.contains("Rated flow 40 l/min"));
+        Ok(())
     }
 
+    #[cfg(unix)]
     #[test]
-    fn ocr_payload_fallback_text_split_by_form_feed() {
-        let response = LlmOcrResponse {
-            pages: None,
-            text: Some("First\u{000C}Second\n".to_string()),
-        };
+    fn scratch_dirs_are_private() -> Result<(), IngestError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let scratch = ScratchDir::create()?;
+        let mode = std::fs::metadata(scratch.path())?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        Ok(())
+    }
+
+    #[test]
+    fn owner_only_encryption_opens_with_empty_password() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("restricted.pdf");
+        let mut document = build_pdf(&["Printing disabled"]);
+        encrypt_rc4(&mut document, "");
+        document.save(&path)?;
+
+        let pages = LopdfExtractor::default().extract_pages(&path)?;
+        assert!(pages[0].text.contains("Printing disabled"));
+        Ok(())
+    }
+
+    fn page(number: u32, text: &str, method: ExtractionMethod) -> PageText {
+        PageText {
+            number,
+            text: text.to_string(),
+            method,
+            confidence: None,
+            section: None,
+            spans: Vec::new(),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn subset_pages_map_back_to_original_numbers() -> Result<(), Box<dyn std::error::Error>> {
+        let document = build_pdf(&["first page", "second page", "third page"]);
+        let subset = lopdf::Document::load_mem(&subset_pdf(&document, &[2, 3])?)?;
+        assert_eq!(subset.get_pages().len(), 2);
+        assert!(subset.extract_text(&[1])?.contains("second page"));
+
+        let renumbered = renumber_subset_pages(
+            vec![
+                page(1, "second", ExtractionMethod::Ocr),
+                page(2, "third", ExtractionMethod::Ocr),
+                page(3, "bogus", ExtractionMethod::Ocr),
+            ],
+            &[2, 3],
+        );
+        assert_eq!(
+            renumbered
+                .iter()
+                .map(|page| page.number)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_document_keeps_native_pages_without_ocr() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("mixed.pdf");
+        write_pdf(&path, &["Hydraulic pump start-up procedure", ""]);
 
-        let pages = payload_to_pages(&response, Path::new("x.pdf"))
-            .expect("multimodal response should be parsed");
+        let pages = extract_page_texts(&path, &IngestionOptions::default())?;
 
         assert_eq!(pages.len(), 2);
-        assert_eq!(pages[0].number, 1);
-        assert_eq!(pages[0].text, "First");
-        assert_eq!(pages[1].number, 2);
-        assert_eq!(pages[1].text, "Second");
+        assert!(pages[0].text.contains("Hydraulic pump"));
+        assert!(pages[1].text.is_empty());
+        Ok(())
     }
 }
@@ -0,0 +1,453 @@
+use crate::{SearchCandidate, SearchError, SearchMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// One backend's hits in rank order, with the weight its mode carries.
+#[derive(Debug, Clone, Copy)]
+pub struct RankedHits<'a> {
+    pub mode: SearchMode,
+    pub weight: f64,
+    pub hits: &'a [SearchCandidate],
+}
+
+/// A fused score and the part of it each mode contributed; the
+/// contributions add up to `score`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FusedScore {
+    pub score: f64,
+    pub contributions: Vec<(SearchMode, f64)>,
+}
+
+impl FusedScore {
+    fn add(&mut self, mode: SearchMode, contribution: f64) {
+        self.score += contribution;
+        self.contributions.push((mode, contribution));
+    }
+}
+
+/// Combines per-backend rank lists into one score per chunk id; higher is better.
+pub trait FusionStrategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore>;
+}
+
+/// Reciprocal rank fusion: `Σ weight / (k + rank)`, ignoring raw scores.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedRrf {
+    pub k: f64,
+}
+
+impl Default for WeightedRrf {
+    fn default() -> Self {
+        Self { k: 60.0 }
+    }
+}
+
+impl FusionStrategy for WeightedRrf {
+    fn name(&self) -> &str {
+        "weighted_rrf"
+    }
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore> {
+        let mut fused = HashMap::<String, FusedScore>::new();
+        for list in lists {
+            for (position, hit) in list.hits.iter().enumerate() {
+                fused
+                    .entry(hit.chunk_id.clone())
+                    .or_default()
+                    .add(list.mode, list.weight / (self.k + position as f64 + 1.0));
+            }
+        }
+        fused
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreNormalization {
+    /// Rescales each list to `0..=1`; a list whose scores are all equal maps to 1.
+    #[default]
+    MinMax,
+    /// Standard score per list; a list without spread maps to 0.
+    ZScore,
+}
+
+impl ScoreNormalization {
+    fn normalize(self, hits: &[SearchCandidate]) -> Vec<f64> {
+        let scores = hits.iter().map(|hit| hit.score).collect::<Vec<_>>();
+        if scores.is_empty() {
+            return scores;
+        }
+        match self {
+            ScoreNormalization::MinMax => {
+                let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                scores
+                    .iter()
+                    .map(|score| {
+                        if max > min {
+                            (score - min) / (max - min)
+                        } else {
+                            1.0
+                        }
+                    })
+                    .collect()
+            }
+            ScoreNormalization::ZScore => {
+                let count = scores.len() as f64;
+                let mean = scores.iter().sum::<f64>() / count;
+                let deviation = (scores
+                    .iter()
+                    .map(|score| (score - mean).powi(2))
+                    .sum::<f64>()
+                    / count)
+                    .sqrt();
+                scores
+                    .iter()
+                    .map(|score| {
+                        if deviation > 0.0 {
+                            (score - mean) / deviation
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for ScoreNormalization {
+    type Err = SearchError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "min-max" | "minmax" => Ok(ScoreNormalization::MinMax),
+            "z-score" | "zscore" => Ok(ScoreNormalization::ZScore),
+            other => Err(SearchError::InvalidQuery(format!(
+                "unknown score normalization `{other}` (expected min-max or z-score)"
+            ))),
+        }
+    }
+}
+
+/// Weighted sum of normalised scores.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CombSum {
+    pub normalization: ScoreNormalization,
+}
+
+impl FusionStrategy for CombSum {
+    fn name(&self) -> &str {
+        "comb_sum"
+    }
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore> {
+        comb(lists, self.normalization)
+    }
+}
+
+/// CombSUM multiplied by the number of lists that returned the chunk.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CombMnz {
+    pub normalization: ScoreNormalization,
+}
+
+impl FusionStrategy for CombMnz {
+    fn name(&self) -> &str {
+        "comb_mnz"
+    }
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore> {
+        let mut fused = comb(lists, self.normalization);
+        for fused in fused.values_mut() {
+            let lists = fused.contributions.len() as f64;
+            fused.score *= lists;
+            for (_, contribution) in &mut fused.contributions {
+                *contribution *= lists;
+            }
+        }
+        fused
+    }
+}
+
+fn comb(
+    lists: &[RankedHits<'_>],
+    normalization: ScoreNormalization,
+) -> HashMap<String, FusedScore> {
+    let mut fused = HashMap::<String, FusedScore>::new();
+    for list in lists {
+        for (hit, score) in list.hits.iter().zip(normalization.normalize(list.hits)) {
+            fused
+                .entry(hit.chunk_id.clone())
+                .or_default()
+                .add(list.mode, list.weight * score);
+        }
+    }
+    fused
+}
+
+/// Keeps the keyword ranking intact and only lets the other lists fill the
+/// slots keyword search left empty, in list order. Weights are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeywordFirst;
+
+impl FusionStrategy for KeywordFirst {
+    fn name(&self) -> &str {
+        "keyword_first"
+    }
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore> {
+        let tiers = lists
+            .iter()
+            .filter(|list| list.mode == SearchMode::Keyword)
+            .chain(lists.iter().filter(|list| list.mode != SearchMode::Keyword));
+        let tier_count = lists.len() as f64;
+
+        let mut fused = HashMap::new();
+        for (tier, list) in tiers.enumerate() {
+            for (position, hit) in list.hits.iter().enumerate() {
+                // `1 / (rank + 1)` stays within (0, 1], so every hit of an
+                // earlier tier outranks every hit of a later one.
+                fused.entry(hit.chunk_id.clone()).or_insert_with(|| {
+                    let mut fused = FusedScore::default();
+                    fused.add(
+                        list.mode,
+                        tier_count - tier as f64 + 1.0 / (position as f64 + 1.0),
+                    );
+                    fused
+                });
+            }
+        }
+        fused
+    }
+}
+
+/// Fusion strategy chosen per query.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Fusion {
+    WeightedRrf { k: f64 },
+    CombSum { normalization: ScoreNormalization },
+    CombMnz { normalization: ScoreNormalization },
+    KeywordFirst,
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::WeightedRrf {
+            k: WeightedRrf::default().k,
+        }
+    }
+}
+
+impl Fusion {
+    /// Builds a strategy from its CLI name (`rrf`, `combsum`, `combmnz`,
+    /// `keyword-first`); `normalization` and `rrf_k` apply where relevant.
+    pub fn from_name(
+        name: &str,
+        normalization: ScoreNormalization,
+        rrf_k: f64,
+    ) -> Result<Self, SearchError> {
+        match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "rrf" | "weighted-rrf" if rrf_k > 0.0 => Ok(Fusion::WeightedRrf { k: rrf_k }),
+            "rrf" | "weighted-rrf" => Err(SearchError::InvalidQuery(format!(
+                "rrf k must be positive, got {rrf_k}"
+            ))),
+            "combsum" | "comb-sum" => Ok(Fusion::CombSum { normalization }),
+            "combmnz" | "comb-mnz" => Ok(Fusion::CombMnz { normalization }),
+            "keyword-first" => Ok(Fusion::KeywordFirst),
+            other => Err(SearchError::InvalidQuery(format!(
+                "unknown fusion strategy `{other}` (expected rrf, combsum, combmnz or keyword-first)"
+            ))),
+        }
+    }
+}
+
+impl FusionStrategy for Fusion {
+    fn name(&self) -> &str {
+        match self {
+            Fusion::WeightedRrf { .. } => "weighted_rrf",
+            Fusion::CombSum { .. } => "comb_sum",
+            Fusion::CombMnz { .. } => "comb_mnz",
+            Fusion::KeywordFirst => "keyword_first",
+        }
+    }
+
+    fn fuse(&self, lists: &[RankedHits<'_>]) -> HashMap<String, FusedScore> {
+        match *self {
+            Fusion::WeightedRrf { k } => WeightedRrf { k }.fuse(lists),
+            Fusion::CombSum { normalization } => CombSum { normalization }.fuse(lists),
+            Fusion::CombMnz { normalization } => CombMnz { normalization }.fuse(lists),
+            Fusion::KeywordFirst => KeywordFirst.fuse(lists),
+        }
+    }
+}
+
+impl fmt::Display for Fusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fusion::WeightedRrf { k } => write!(f, "weighted_rrf(k={k})"),
+            Fusion::CombSum { normalization } | Fusion::CombMnz { normalization } => {
+                let normalization = match normalization {
+                    ScoreNormalization::MinMax => "min_max",
+                    ScoreNormalization::ZScore => "z_score",
+                };
+                write!(f, "{}({normalization})", self.name())
+            }
+            Fusion::KeywordFirst => write!(f, "keyword_first"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(mode: SearchMode, ranked: &[(&str, f64)]) -> Vec<SearchCandidate> {
+        ranked
+            .iter()
+            .map(|&(chunk_id, score)| SearchCandidate {
+                chunk_id: chunk_id.to_string(),
+                document_id: "doc-1".to_string(),
+                source_path: "/tmp/doc.pdf".to_string(),
+                score,
+                source: String::new(),
+                chunk: None,
+                text: None,
+                mode,
+                ocr_confidence: None,
+                citation: None,
+                clause_id: None,
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights: Vec::new(),
+                explanation: None,
+            })
+            .collect()
+    }
+
+    fn ranking(fused: HashMap<String, FusedScore>) -> Vec<String> {
+        let mut ranked = fused.into_iter().collect::<Vec<_>>();
+        ranked.sort_by(|left, right| {
+            right
+                .1
+                .score
+                .total_cmp(&left.1.score)
+                .then(left.0.cmp(&right.0))
+        });
+        ranked.into_iter().map(|(chunk_id, _)| chunk_id).collect()
+    }
+
+    #[test]
+    fn weighted_rrf_uses_ranks_and_weights_only() {
+        // Raw scores are wildly different in scale and must not matter.
+        let keyword = hits(SearchMode::Keyword, &[("a", 250.0), ("b", 3.0), ("c", 1.0)]);
+        let vector = hits(SearchMode::Vector, &[("c", 0.91), ("b", 0.90)]);
+        let lists = [
+            RankedHits {
+                mode: SearchMode::Keyword,
+                weight: 0.55,
+                hits: &keyword,
+            },
+            RankedHits {
+                mode: SearchMode::Vector,
+                weight: 0.35,
+                hits: &vector,
+            },
+        ];
+
+        let fused = WeightedRrf { k: 60.0 }.fuse(&lists);
+
+        assert!((fused["a"].score - 0.55 / 61.0).abs() < 1e-12);
+        assert!((fused["c"].score - (0.55 / 63.0 + 0.35 / 61.0)).abs() < 1e-12);
+        assert_eq!(ranking(fused), vec!["b", "c", "a"]);
+
+        let keyword_only = WeightedRrf { k: 60.0 }.fuse(&lists[..1]);
+        assert_eq!(ranking(keyword_only), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn comb_strategies_normalise_each_list() {
+        let keyword = hits(
+            SearchMode::Keyword,
+            &[("a", 30.0), ("b", 20.0), ("c", 10.0)],
+        );
+        let vector = hits(SearchMode::Vector, &[("d", 0.75), ("c", 0.5), ("e", 0.25)]);
+        let lists = [
+            RankedHits {
+                mode: SearchMode::Keyword,
+                weight: 1.0,
+                hits: &keyword,
+            },
+            RankedHits {
+                mode: SearchMode::Vector,
+                weight: 1.0,
+                hits: &vector,
+            },
+        ];
+
+        let sum = CombSum::default().fuse(&lists);
+        assert!((sum["a"].score - 1.0).abs() < 1e-9);
+        assert!((sum["c"].score - 0.5).abs() < 1e-9);
+        assert_eq!(ranking(sum), vec!["a", "d", "b", "c", "e"]);
+
+        // Being found by both lists doubles c under CombMNZ.
+        let mnz = CombMnz::default().fuse(&lists);
+        assert!((mnz["c"].score - 1.0).abs() < 1e-9);
+        assert_eq!(
+            mnz["c"].contributions,
+            vec![(SearchMode::Keyword, 0.0), (SearchMode::Vector, 1.0)]
+        );
+        assert_eq!(ranking(mnz), vec!["a", "c", "d", "b", "e"]);
+
+        let z = CombSum {
+            normalization: ScoreNormalization::ZScore,
+        }
+        .fuse(&lists);
+        let spread = (2.0f64 / 3.0).sqrt();
+        assert!((z["a"].score - 1.0 / spread).abs() < 1e-9);
+        assert!((z["c"].score + 1.0 / spread).abs() < 1e-9);
+    }
+
+    #[test]
+    fn keyword_first_only_rescues_with_vector_hits() {
+        let keyword = hits(SearchMode::Keyword, &[("clause", 2.0), ("table", 1.0)]);
+        let vector = hits(SearchMode::Vector, &[("similar", 0.99), ("table", 0.95)]);
+        let graph = hits(SearchMode::Graph, &[("neighbour", 1.0)]);
+        let lists = [
+            RankedHits {
+                mode: SearchMode::Vector,
+                weight: 0.35,
+                hits: &vector,
+            },
+            RankedHits {
+                mode: SearchMode::Keyword,
+                weight: 0.55,
+                hits: &keyword,
+            },
+            RankedHits {
+                mode: SearchMode::Graph,
+                weight: 0.10,
+                hits: &graph,
+            },
+        ];
+
+        let fused = KeywordFirst.fuse(&lists);
+
+        assert_eq!(
+            ranking(fused),
+            vec!["clause", "table", "similar", "neighbour"]
+        );
+        assert_eq!(
+            Fusion::from_name("keyword_first", ScoreNormalization::MinMax, 60.0)
+                .expect("known strategy"),
+            Fusion::KeywordFirst
+        );
+        assert!(Fusion::from_name("borda", ScoreNormalization::MinMax, 60.0).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{Highlight, SearchQuery};
+
+/// Approximate length of a snippet in characters.
+pub const SNIPPET_CHARS: usize = 150;
+/// Most snippets kept per hit.
+pub const MAX_SNIPPETS: usize = 3;
+
+/// Tags OpenSearch is asked to wrap matches in.
+pub(crate) const PRE_TAG: &str = "<em>";
+pub(crate) const POST_TAG: &str = "</em>";
+
+/// Snippets of `text` around the words of the query text and its phrases,
+/// for hits the keyword store did not highlight.
+pub fn highlight(text: &str, query: &SearchQuery) -> Vec<Highlight> {
+    let patterns = query
+        .text
+        .split_whitespace()
+        .chain(query.phrases.iter().map(String::as_str))
+        .map(|pattern| {
+            words(pattern)
+                .into_iter()
+                .map(|(start, end)| pattern[start..end].to_lowercase())
+                .collect::<Vec<_>>()
+        })
+        .filter(|pattern| pattern.len() > 1 || pattern.first().is_some_and(|term| term.len() >= 3))
+        .collect::<Vec<_>>();
+    let words = words(text);
+    let lowered = words
+        .iter()
+        .map(|&(start, end)| text[start..end].to_lowercase())
+        .collect::<Vec<_>>();
+
+    let mut matches = Vec::new();
+    for pattern in &patterns {
+        for first in 0..lowered.len() {
+            let Some(candidate) = lowered.get(first..first + pattern.len()) else {
+                break;
+            };
+            // Single terms also match inflections ("pump" in "pumps").
+            let matched = match pattern.as_slice() {
+                [term] => candidate[0].starts_with(term.as_str()),
+                _ => candidate == pattern.as_slice(),
+            };
+            if matched {
+                matches.push((words[first].0, words[first + pattern.len() - 1].1));
+            }
+        }
+    }
+    matches.sort_unstable();
+    let mut merged = Vec::<(usize, usize)>::new();
+    for (start, end) in matches {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    fragments(text, &merged)
+}
+
+/// Reads an OpenSearch highlight fragment, turning its tags into offsets.
+pub(crate) fn from_tagged(tagged: &str) -> Highlight {
+    let mut fragment = String::with_capacity(tagged.len());
+    let mut matches = Vec::new();
+    let mut rest = tagged;
+    while let Some(open) = rest.find(PRE_TAG) {
+        fragment.push_str(&rest[..open]);
+        rest = &rest[open + PRE_TAG.len()..];
+        let close = rest.find(POST_TAG).unwrap_or(rest.len());
+        let start = fragment.len();
+        fragment.push_str(&rest[..close]);
+        matches.push((start, fragment.len()));
+        rest = rest.get(close + POST_TAG.len()..).unwrap_or_default();
+    }
+    fragment.push_str(rest);
+    Highlight { fragment, matches }
+}
+
+/// Byte ranges of the alphanumeric runs in `text`.
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(begin)) => {
+                words.push((begin, index));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push((begin, text.len()));
+    }
+    words
+}
+
+/// Cuts snippets of about `SNIPPET_CHARS` starting a little before each
+/// match not covered by an earlier snippet, on word boundaries.
+fn fragments(text: &str, matches: &[(usize, usize)]) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    let mut next = 0;
+    while next < matches.len() && highlights.len() < MAX_SNIPPETS {
+        let (first_start, first_end) = matches[next];
+        let start = text[..first_start]
+            .char_indices()
+            .rev()
+            .take(SNIPPET_CHARS / 4)
+            .find(|(_, c)| c.is_whitespace())
+            .map_or_else(
+                || {
+                    text[..first_start]
+                        .char_indices()
+                        .rev()
+                        .nth(SNIPPET_CHARS / 4)
+                        .map_or(0, |(index, _)| index)
+                },
+                |(index, c)| index + c.len_utf8(),
+            );
+        let limit = text[start..]
+            .char_indices()
+            .nth(SNIPPET_CHARS)
+            .map_or(text.len(), |(index, _)| start + index)
+            .max(first_end);
+        let end = if limit == text.len() {
+            limit
+        } else {
+            text[first_end..limit]
+                .rfind(char::is_whitespace)
+                .map_or(limit, |index| first_end + index)
+        };
+
+        let mut covered = Vec::new();
+        while next < matches.len() && matches[next].1 <= end {
+            covered.push((matches[next].0 - start, matches[next].1 - start));
+            next += 1;
+        }
+        highlights.push(Highlight {
+            fragment: text[start..end].to_string(),
+            matches: covered,
+        });
+    }
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marked(highlight: &Highlight) -> Vec<&str> {
+        highlight
+            .matches
+            .iter()
+            .map(|&(start, end)| &highlight.fragment[start..end])
+            .collect()
+    }
+
+    #[test]
+    fn local_snippets_mark_terms_and_phrases_near_their_matches() {
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            phrases: vec!["set pressure".to_string()],
+            ..SearchQuery::default()
+        };
+        let text = format!(
+            "{} The Relief valves open at the set pressure. {} Check each valve yearly.",
+            "Preamble words. ".repeat(20),
+            "Unrelated filler text. ".repeat(10)
+        );
+
+        let highlights = highlight(&text, &query);
+        assert_eq!(highlights.len(), 2);
+        assert!(highlights[0].fragment.len() <= SNIPPET_CHARS + 10);
+        assert_eq!(
+            marked(&highlights[0]),
+            vec!["Relief", "valves", "set pressure"]
+        );
+        assert_eq!(marked(&highlights[1]), vec!["valve"]);
+        assert!(highlight("nothing relevant", &query).is_empty());
+    }
+
+    #[test]
+    fn opensearch_tags_become_offsets() {
+        let highlight = from_tagged("open the <em>relief</em> <em>valve</em> slowly");
+        assert_eq!(highlight.fragment, "open the relief valve slowly");
+        assert_eq!(marked(&highlight), vec!["relief", "valve"]);
+    }
+}
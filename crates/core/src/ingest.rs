@@ -1,37 +1,46 @@
+use crate::archives::{container_kind, expand_container};
+use crate::discovery::{discover_files, DiscoveredFiles, DiscoveryPolicy, UnreadablePath};
+use crate::error::FailureCategory;
+use crate::extractor::{ExtractionMethod, PageText};
+use crate::ocr_cache::{OcrCache, OcrCacheStats};
+use crate::sources::{DocumentFormat, DocumentSource};
 use crate::{
-    build_chunks, chunking::normalize_whitespace, extract_page_texts, DocumentFingerprint,
-    IngestError, IngestionOptions, PdfChunk,
+    build_chunks, chunking::normalize_whitespace, DocumentFingerprint, IngestError,
+    IngestionOptions, PdfChunk,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::time::Instant;
 
+/// Every `.pdf` under `folder`, hidden or ignored ones included; use
+/// `discover_documents` to apply a `DiscoveryPolicy`.
 pub fn discover_pdf_files(folder: &Path) -> Vec<PathBuf> {
-    let mut files = Vec::new();
-
-    for entry in WalkDir::new(folder)
-        .into_iter()
-        .filter_map(|item| item.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-
-        let is_pdf = entry
-            .path()
-            .extension()
+    let everything = DiscoveryPolicy {
+        include_hidden: true,
+        use_ignore_files: false,
+        ..DiscoveryPolicy::default()
+    };
+    discover_files(folder, &everything, |path| {
+        path.extension()
             .and_then(|ext| ext.to_str())
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
-
-        if is_pdf {
-            files.push(entry.path().to_path_buf());
-        }
-    }
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+    })
+    .map(|discovered| discovered.files)
+    .unwrap_or_default()
+}
 
-    files.sort_unstable();
-    files
+pub fn discover_documents(
+    folder: &Path,
+    options: &IngestionOptions,
+) -> Result<DiscoveredFiles, IngestError> {
+    discover_files(folder, &options.discovery, |path| {
+        options.sources.for_path(path).is_some()
+            || (options.expand_archives && container_kind(path).is_some())
+    })
 }
 
 pub fn digest_file(path: &Path) -> Result<String, IngestError> {
@@ -49,86 +58,401 @@ pub fn ingest_folder_chunks(
     Ok(report.chunks)
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkippedPdf {
     pub path: PathBuf,
+    pub category: FailureCategory,
     pub reason: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Ingested,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IngestWarning {
+    EmptyPage {
+        page: u32,
+    },
+    LowOcrConfidence {
+        page: u32,
+        confidence: f32,
+    },
+    /// An extractor failed on these pages and no other step recovered their text.
+    OcrFailed {
+        pages: Vec<u32>,
+        reason: String,
+    },
+    NoChunks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<DocumentFormat>,
+    pub status: FileStatus,
+    pub checksum: Option<String>,
+    pub page_count: usize,
+    pub chunk_count: usize,
+    pub extraction_methods: Vec<ExtractionMethod>,
+    pub duration_ms: u64,
+    pub warnings: Vec<IngestWarning>,
+    pub failure: Option<FailureCategory>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IngestionReport {
-    pub chunks: Vec<PdfChunk>,
+    pub folder: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub files: Vec<FileReport>,
     pub skipped_files: Vec<SkippedPdf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ocr_cache: Option<OcrCacheStats>,
+    #[serde(default)]
+    pub unreadable_paths: Vec<UnreadablePath>,
+    /// Whether the chunks reached the stores; set by the caller after indexing.
+    #[serde(default)]
+    pub indexing: IndexingStatus,
+    #[serde(skip)]
+    pub chunks: Vec<PdfChunk>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum IndexingStatus {
+    #[default]
+    Pending,
+    Indexed,
+    Failed {
+        error: String,
+    },
+}
+
+impl IngestionReport {
+    pub fn chunk_count(&self) -> usize {
+        self.files.iter().map(|file| file.chunk_count).sum()
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<(), IngestError> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Writes the report into `dir` as `ingest-<started_at>.json`, with
+    /// microseconds and a numeric suffix so that reports never overwrite
+    /// each other.
+    pub fn write_json_in(&self, dir: &Path) -> Result<PathBuf, IngestError> {
+        fs::create_dir_all(dir)?;
+        let stamp = self.started_at.format("%Y%m%dT%H%M%S%.6fZ");
+        let bytes = serde_json::to_vec_pretty(self)?;
+        for attempt in 0u32.. {
+            let name = match attempt {
+                0 => format!("ingest-{stamp}.json"),
+                _ => format!("ingest-{stamp}-{attempt}.json"),
+            };
+            let path = dir.join(name);
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    file.write_all(&bytes)?;
+                    return Ok(path);
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        unreachable!("report names are exhausted")
+    }
+}
+
+struct FileOutcome {
+    checksum: String,
+    page_count: usize,
+    extraction_methods: Vec<ExtractionMethod>,
+    warnings: Vec<IngestWarning>,
+    chunks: Vec<PdfChunk>,
 }
 
 pub fn ingest_folder_chunks_best_effort(
     folder: &Path,
     options: IngestionOptions,
 ) -> Result<IngestionReport, IngestError> {
-    let files = discover_pdf_files(folder);
+    let discovered = discover_documents(folder, &options)?;
 
-    if files.is_empty() {
+    if discovered.files.is_empty() && discovered.oversized.is_empty() {
         return Err(IngestError::InvalidArgument(format!(
-            "no pdf files found in {}",
+            "no supported documents ({}) found in {}",
+            options
+                .sources
+                .formats()
+                .iter()
+                .map(DocumentFormat::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
             folder.display()
         )));
     }
 
-    let mut result = Vec::new();
-    let mut skipped_files = Vec::new();
+    Ok(ingest_discovered(folder, discovered, options))
+}
+
+/// Ingests files that were already discovered, e.g. the subset a folder
+/// watcher saw change, without walking the folder again.
+pub fn ingest_discovered(
+    folder: &Path,
+    discovered: DiscoveredFiles,
+    options: IngestionOptions,
+) -> IngestionReport {
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let cache_before = options.ocr_cache.as_ref().map(OcrCache::stats);
+    let mut report = ReportBuilder::default();
     let mut cursor = 0u64;
 
-    for path in files {
-        let build_result = (|| {
-            let fingerprint = build_document_fingerprint(&path)?;
-            let pages = extract_page_texts(&path)?;
-            let mut chunks = Vec::new();
-
-            for page in pages {
-                let normalized = normalize_whitespace(&page.text);
-                let (page_chunks, next_cursor) = build_chunks(
-                    &fingerprint,
-                    page.number,
-                    "unassigned",
-                    None,
-                    &normalized,
-                    &options,
-                    cursor,
-                )?;
-
-                cursor = next_cursor;
-                chunks.extend(page_chunks);
+    for (path, size) in discovered.oversized {
+        let format = options
+            .sources
+            .for_path(&path)
+            .map(|source| source.format());
+        let error = IngestError::TooLarge(format!(
+            "{} is {size} bytes, above the discovery limit",
+            path.display()
+        ));
+        report.record(path, format, Err(error), 0);
+    }
+
+    for path in discovered.files {
+        let container_started = Instant::now();
+        let (_container, documents) = if options.expand_archives && container_kind(&path).is_some()
+        {
+            match expand_container(&path, &path, &options) {
+                Ok(container) => {
+                    let documents = container
+                        .members
+                        .iter()
+                        .map(|member| (member.path.clone(), member.virtual_path.clone()))
+                        .collect::<Vec<_>>();
+                    (Some(container), documents)
+                }
+                Err(error) => {
+                    report.record(path, None, Err(error), elapsed_ms(container_started));
+                    continue;
+                }
             }
+        } else {
+            (None, vec![(path.clone(), path)])
+        };
+
+        for (path, source_path) in documents {
+            let file_started = Instant::now();
+            let source = options.sources.for_path(&path);
+            let build_result = match source {
+                Some(source) => ingest_file(&path, &source_path, source, &options, &mut cursor),
+                None => Err(IngestError::Unsupported(format!(
+                    "no document source for {}",
+                    source_path.display()
+                ))),
+            };
+            report.record(
+                source_path,
+                source.map(|source| source.format()),
+                build_result,
+                elapsed_ms(file_started),
+            );
+        }
+    }
+
+    IngestionReport {
+        folder: folder.to_path_buf(),
+        started_at,
+        finished_at: Utc::now(),
+        duration_ms: elapsed_ms(started),
+        files: report.files,
+        skipped_files: report.skipped_files,
+        ocr_cache: options
+            .ocr_cache
+            .as_ref()
+            .zip(cache_before)
+            .map(|(cache, before)| cache.stats().since(&before)),
+        unreadable_paths: discovered.unreadable,
+        indexing: IndexingStatus::Pending,
+        chunks: report.chunks,
+    }
+}
+
+#[derive(Default)]
+struct ReportBuilder {
+    files: Vec<FileReport>,
+    skipped_files: Vec<SkippedPdf>,
+    chunks: Vec<PdfChunk>,
+}
+
+impl ReportBuilder {
+    fn record(
+        &mut self,
+        path: PathBuf,
+        format: Option<DocumentFormat>,
+        result: Result<FileOutcome, IngestError>,
+        duration_ms: u64,
+    ) {
+        match result {
+            Ok(outcome) => {
+                self.files.push(FileReport {
+                    path,
+                    format,
+                    status: FileStatus::Ingested,
+                    checksum: Some(outcome.checksum),
+                    page_count: outcome.page_count,
+                    chunk_count: outcome.chunks.len(),
+                    extraction_methods: outcome.extraction_methods,
+                    duration_ms,
+                    warnings: outcome.warnings,
+                    failure: None,
+                    error: None,
+                });
+                self.chunks.extend(outcome.chunks);
+            }
+            Err(error) => {
+                let category = error.category();
+                let reason = error.to_string();
+                self.files.push(FileReport {
+                    path: path.clone(),
+                    format,
+                    status: FileStatus::Skipped,
+                    checksum: None,
+                    page_count: 0,
+                    chunk_count: 0,
+                    extraction_methods: Vec::new(),
+                    duration_ms,
+                    warnings: Vec::new(),
+                    failure: Some(category),
+                    error: Some(reason.clone()),
+                });
+                self.skipped_files.push(SkippedPdf {
+                    path,
+                    category,
+                    reason,
+                });
+            }
+        }
+    }
+}
+
+fn ingest_file(
+    path: &Path,
+    source_path: &Path,
+    source: &dyn DocumentSource,
+    options: &IngestionOptions,
+    cursor: &mut u64,
+) -> Result<FileOutcome, IngestError> {
+    let fingerprint = build_document_fingerprint(path, source_path)?;
+    let pages = source.extract(path, options)?;
+    let page_count = pages.len();
+    let mut extraction_methods = Vec::new();
+    let mut warnings = Vec::new();
+    let mut chunks = Vec::new();
+    let mut next = *cursor;
+    let mut runs = Vec::<(String, Vec<PageText>)>::new();
+
+    for page in pages {
+        if !extraction_methods.contains(&page.method) {
+            extraction_methods.push(page.method);
+        }
+        if let Some(reason) = &page.failure {
+            match warnings.iter_mut().find(|warning| {
+                matches!(warning, IngestWarning::OcrFailed { reason: existing, .. } if existing == reason)
+            }) {
+                Some(IngestWarning::OcrFailed { pages, .. }) => pages.push(page.number),
+                _ => warnings.push(IngestWarning::OcrFailed {
+                    pages: vec![page.number],
+                    reason: reason.clone(),
+                }),
+            }
+        }
+
+        if normalize_whitespace(&page.text).is_empty() {
+            warnings.push(IngestWarning::EmptyPage { page: page.number });
+            continue;
+        }
 
-            Ok::<_, IngestError>(chunks)
-        })();
+        if let Some(confidence) = page
+            .confidence
+            .filter(|confidence| *confidence < options.low_ocr_confidence)
+        {
+            warnings.push(IngestWarning::LowOcrConfidence {
+                page: page.number,
+                confidence,
+            });
+        }
 
-        match build_result {
-            Ok(file_chunks) => result.extend(file_chunks),
-            Err(error) => skipped_files.push(SkippedPdf {
-                path,
-                reason: error.to_string(),
-            }),
+        let section = page.section.as_deref().unwrap_or("unassigned");
+        match runs.last_mut() {
+            Some((run_section, run_pages)) if run_section == section => run_pages.push(page),
+            _ => runs.push((section.to_string(), vec![page])),
         }
     }
 
-    Ok(IngestionReport {
-        chunks: result,
-        skipped_files,
+    for (section, run_pages) in runs {
+        let (run_chunks, next_cursor) =
+            build_chunks(&fingerprint, &run_pages, &section, None, options, next)?;
+        next = next_cursor;
+        chunks.extend(run_chunks);
+    }
+
+    if chunks.is_empty() {
+        warnings.push(IngestWarning::NoChunks);
+    }
+
+    *cursor = next;
+    Ok(FileOutcome {
+        checksum: fingerprint.checksum,
+        page_count,
+        extraction_methods,
+        warnings,
+        chunks,
     })
 }
 
-fn build_document_fingerprint(path: &Path) -> Result<DocumentFingerprint, IngestError> {
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+fn build_document_fingerprint(
+    path: &Path,
+    source_path: &Path,
+) -> Result<DocumentFingerprint, IngestError> {
     let checksum = digest_file(path)?;
-    let name = path
+    let name = source_path
         .file_name()
         .and_then(|name| name.to_str())
         .ok_or_else(|| {
-            IngestError::MissingFileName(format!("path missing filename: {}", path.display()))
+            IngestError::MissingFileName(format!(
+                "path missing filename: {}",
+                source_path.display()
+            ))
         })?;
 
     Ok(DocumentFingerprint {
-        document_id: generate_document_id(path),
+        document_id: generate_document_id(source_path),
         document_title: name.to_string(),
-        source_path: path.to_string_lossy().to_string(),
+        source_path: source_path.to_string_lossy().to_string(),
         version: None,
         standard: None,
         checksum,
@@ -136,7 +460,7 @@ fn build_document_fingerprint(path: &Path) -> Result<DocumentFingerprint, Ingest
     })
 }
 
-fn generate_document_id(path: &Path) -> String {
+pub fn generate_document_id(path: &Path) -> String {
     let mut hasher = Sha256::new();
     hasher.update(path.to_string_lossy().as_bytes());
     format!("{:x}", hasher.finalize())
@@ -144,10 +468,19 @@ fn generate_document_id(path: &Path) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{digest_file, discover_pdf_files, ingest_folder_chunks_best_effort};
-    use crate::IngestionOptions;
+    use super::{
+        digest_file, discover_pdf_files, ingest_folder_chunks_best_effort, FileStatus,
+        IngestWarning,
+    };
+    use crate::extractor::{LopdfExtractor, PageText, PdfExtractor};
+    use crate::test_pdf::write_pdf;
+    use crate::{
+        DiscoveryPolicy, DocumentFormat, ExtractorChain, FailureCategory, IngestError,
+        IngestionOptions, PageAcceptance, SourceRegistry,
+    };
     use std::fs::{self, File};
     use std::io::Write;
+    use std::path::Path;
     use tempfile::tempdir;
 
     #[test]
@@ -163,6 +496,12 @@ mod tests {
 
         let files = discover_pdf_files(base);
         assert_eq!(files.len(), 2);
+
+        let hidden = base.join(".archive");
+        fs::create_dir(&hidden)?;
+        fs::write(hidden.join("c.pdf"), b"%PDF-1.4\n%fake")?;
+        fs::write(base.join(".docneedleignore"), "a.pdf\n")?;
+        assert_eq!(discover_pdf_files(base).len(), 3);
         Ok(())
     }
 
@@ -204,6 +543,163 @@ mod tests {
                 .and_then(|name| name.to_str()),
             Some("unreadable.pdf")
         );
+        assert_eq!(report.skipped_files[0].category, FailureCategory::Corrupt);
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].status, FileStatus::Skipped);
+        assert_eq!(report.files[0].failure, Some(FailureCategory::Corrupt));
+        Ok(())
+    }
+
+    struct DownOcr;
+
+    impl PdfExtractor for DownOcr {
+        fn extract_pages(&self, _path: &Path) -> Result<Vec<PageText>, IngestError> {
+            Err(IngestError::OcrUnavailable("ocr is down".to_string()))
+        }
+    }
+
+    #[test]
+    fn pages_lost_to_a_failed_ocr_step_are_reported() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        write_pdf(
+            &dir.path().join("mixed.pdf"),
+            &["Hydraulic pump start-up procedure and checks", ""],
+        );
+        let options = IngestionOptions {
+            extractors: ExtractorChain::new()
+                .then(LopdfExtractor::default(), PageAcceptance::default())
+                .then(DownOcr, PageAcceptance::any()),
+            ..IngestionOptions::default()
+        };
+
+        let report = ingest_folder_chunks_best_effort(dir.path(), options)?;
+
+        assert_eq!(report.files[0].status, FileStatus::Ingested);
+        assert!(report.files[0].warnings.iter().any(|warning| matches!(
+            warning,
+            IngestWarning::OcrFailed { pages, reason } if pages == &[2] && reason.contains("ocr is down")
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn report_is_written_as_json() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("unreadable.pdf"), b"%PDF-1.4\n%broken")?;
+        let report = ingest_folder_chunks_best_effort(dir.path(), IngestionOptions::default())?;
+
+        let report_path = dir.path().join("reports").join("ingest.json");
+        report.write_json(&report_path)?;
+
+        let written: serde_json::Value = serde_json::from_slice(&fs::read(&report_path)?)?;
+        assert_eq!(written["files"][0]["status"], "skipped");
+        assert_eq!(written["skipped_files"][0]["category"], "corrupt");
+        assert!(written.get("chunks").is_none());
+        assert_eq!(written["indexing"]["state"], "pending");
+
+        let reports = dir.path().join("reports");
+        let first = report.write_json_in(&reports)?;
+        let second = report.write_json_in(&reports)?;
+        assert_ne!(first, second);
+        assert!(first.exists() && second.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn markdown_and_text_documents_are_ingested_by_section(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("procedure.md"),
+            "# Pump\n\n## Start-up\n\nOpen the suction valve before starting the pump motor.",
+        )?;
+        fs::write(dir.path().join("notes.txt"), "Relief valve set to 210 bar.")?;
+        fs::write(dir.path().join("photo.png"), b"\x89PNG")?;
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            ..IngestionOptions::default()
+        };
+        assert!(
+            ingest_folder_chunks_best_effort(dir.path(), options.clone()).is_err(),
+            "only PDFs are ingested by default"
+        );
+
+        let options = IngestionOptions {
+            sources: SourceRegistry::all(),
+            ..options
+        };
+        let report = ingest_folder_chunks_best_effort(dir.path(), options)?;
+
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].format, Some(DocumentFormat::Text));
+        assert_eq!(report.files[1].format, Some(DocumentFormat::Markdown));
+        let start_up = report
+            .chunks
+            .iter()
+            .find(|chunk| chunk.text_raw.contains("suction valve"))
+            .expect("markdown section chunk");
+        assert_eq!(start_up.section_path, "Pump > Start-up");
+        Ok(())
+    }
+
+    #[test]
+    fn archive_members_are_ingested_under_virtual_paths() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let dir = tempdir()?;
+        let bundle = dir.path().join("bundle.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&bundle)?);
+        zip.start_file("manuals/pump.md", zip::write::SimpleFileOptions::default())?;
+        zip.write_all(b"# Pump\n\nOpen the suction valve before starting the pump motor.")?;
+        zip.finish()?;
+
+        let skipped = ingest_folder_chunks_best_effort(dir.path(), IngestionOptions::default());
+        assert!(
+            skipped.is_err(),
+            "archives are ignored unless expansion is enabled"
+        );
+
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            expand_archives: true,
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+        let report = ingest_folder_chunks_best_effort(dir.path(), options)?;
+
+        let member = format!("{}!/manuals/pump.md", bundle.display());
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].path.to_string_lossy(), member);
+        assert!(!report.chunks.is_empty());
+        assert!(report
+            .chunks
+            .iter()
+            .all(|chunk| chunk.source_path == member));
+        Ok(())
+    }
+
+    #[test]
+    fn files_above_the_size_limit_are_reported_as_too_large(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("notes.txt"), "Relief valve set to 210 bar.")?;
+        fs::write(dir.path().join("huge.txt"), "x".repeat(4096))?;
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            discovery: DiscoveryPolicy {
+                max_file_bytes: Some(1024),
+                ..DiscoveryPolicy::default()
+            },
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+
+        let report = ingest_folder_chunks_best_effort(dir.path(), options)?;
+
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.skipped_files.len(), 1);
+        assert!(report.skipped_files[0].path.ends_with("huge.txt"));
+        assert_eq!(report.skipped_files[0].category, FailureCategory::TooLarge);
+        assert!(report.unreadable_paths.is_empty());
         Ok(())
     }
 }
@@ -0,0 +1,652 @@
+use crate::error::IngestError;
+use crate::extractor::{
+    read_subset_text_layer, read_text_layer, renumber_subset_pages, PageText, PdfExtractor,
+};
+use crate::models::BoundingBox;
+use crate::passwords::PasswordMap;
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A run of text drawn by one text-showing operator, positioned in PDF user
+/// space (origin bottom-left). `char_start..char_end` index the characters of
+/// the page's `PageText::text`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub text: String,
+    pub bbox: BoundingBox,
+    pub font_size: f32,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Text-layer extractor that interprets the content stream itself so that
+/// every span keeps its position, and pages are read column by column instead
+/// of in drawing order.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutExtractor {
+    passwords: PasswordMap,
+}
+
+impl LayoutExtractor {
+    pub fn with_passwords(passwords: PasswordMap) -> Self {
+        Self { passwords }
+    }
+}
+
+impl PdfExtractor for LayoutExtractor {
+    fn name(&self) -> &str {
+        "layout"
+    }
+
+    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
+        read_text_layer(path, &self.passwords, |document, _, page_id| {
+            Ok(layout_text(positioned_spans(document, page_id)?))
+        })
+    }
+
+    fn extract_page_subset(
+        &self,
+        pdf: &[u8],
+        source: &Path,
+        page_numbers: &[u32],
+    ) -> Result<Vec<PageText>, IngestError> {
+        let pages = read_subset_text_layer(pdf, source, |document, _, page_id| {
+            Ok(layout_text(positioned_spans(document, page_id)?))
+        })?;
+        Ok(renumber_subset_pages(pages, page_numbers))
+    }
+}
+
+/// Interprets the text operators of one page. Form XObjects are not entered.
+pub fn positioned_spans(
+    document: &Document,
+    page_id: ObjectId,
+) -> Result<Vec<TextSpan>, lopdf::Error> {
+    let fonts = document
+        .get_page_fonts(page_id)
+        .into_iter()
+        .map(|(name, font)| (name, FontMetrics::of(document, font)))
+        .collect::<BTreeMap<_, _>>();
+    let content = Content::decode(&document.get_page_content(page_id)?)?;
+
+    let mut state = GraphicsState::default();
+    let mut saved = Vec::new();
+    let mut text_matrix = Matrix::IDENTITY;
+    let mut line_matrix = Matrix::IDENTITY;
+    let mut spans = Vec::new();
+
+    for operation in &content.operations {
+        let operands = &operation.operands;
+        let number = |index: usize| {
+            operands
+                .get(index)
+                .and_then(|operand| operand.as_float().ok())
+                .unwrap_or(0.0)
+        };
+        match operation.operator.as_str() {
+            "q" => saved.push(state.clone()),
+            "Q" => state = saved.pop().unwrap_or_default(),
+            "cm" => state.ctm = Matrix::from_operands(operands).then(state.ctm),
+            "BT" => {
+                text_matrix = Matrix::IDENTITY;
+                line_matrix = Matrix::IDENTITY;
+            }
+            "Tf" => {
+                state.font = operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .map(<[u8]>::to_vec);
+                state.font_size = number(1);
+            }
+            "Tc" => state.char_spacing = number(0),
+            "Tw" => state.word_spacing = number(0),
+            "Tz" => state.horizontal_scale = number(0) / 100.0,
+            "TL" => state.leading = number(0),
+            "Ts" => state.rise = number(0),
+            "Td" | "TD" => {
+                if operation.operator == "TD" {
+                    state.leading = -number(1);
+                }
+                line_matrix = Matrix::translate(number(0), number(1)).then(line_matrix);
+                text_matrix = line_matrix;
+            }
+            "Tm" => {
+                line_matrix = Matrix::from_operands(operands);
+                text_matrix = line_matrix;
+            }
+            "T*" | "'" | "\"" => {
+                if operation.operator == "\"" {
+                    state.word_spacing = number(0);
+                    state.char_spacing = number(1);
+                }
+                line_matrix = Matrix::translate(0.0, -state.leading).then(line_matrix);
+                text_matrix = line_matrix;
+                if operation.operator != "T*" {
+                    let shown = operands.last().map(std::slice::from_ref).unwrap_or(&[]);
+                    spans.extend(show_text(&state, &fonts, &mut text_matrix, shown));
+                }
+            }
+            "Tj" | "TJ" => {
+                let shown = match operands.first() {
+                    Some(Object::Array(items)) => items.as_slice(),
+                    _ => operands.as_slice(),
+                };
+                spans.extend(show_text(&state, &fonts, &mut text_matrix, shown));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(spans)
+}
+
+/// Orders spans into reading order and assembles the page text, filling in
+/// each span's character offsets. Lines are grouped by baseline; vertical
+/// gutters shared by most lines split the page into columns, which are read
+/// left to right between lines that run across the gutter (titles, footers).
+pub fn layout_text(spans: Vec<TextSpan>) -> (String, Vec<TextSpan>) {
+    let lines = group_lines(spans);
+    let gutters = find_gutters(&lines);
+
+    let mut blocks = Vec::<Vec<Line>>::new();
+    let mut columns = vec![Vec::<Line>::new(); gutters.len() + 1];
+    for line in lines {
+        let crosses = |span: &TextSpan| {
+            gutters
+                .iter()
+                .any(|&middle| span.bbox.x0 < middle && span.bbox.x1 > middle)
+        };
+        if line.spans.iter().any(crosses) {
+            blocks.extend(columns.iter_mut().map(std::mem::take));
+            blocks.push(vec![line]);
+            continue;
+        }
+        let mut parts = vec![Vec::new(); gutters.len() + 1];
+        for span in line.spans {
+            let column = gutters
+                .iter()
+                .filter(|&&middle| span.bbox.x0 >= middle)
+                .count();
+            parts[column].push(span);
+        }
+        for (column, spans) in parts.into_iter().enumerate() {
+            if !spans.is_empty() {
+                columns[column].push(Line {
+                    baseline: line.baseline,
+                    spans,
+                });
+            }
+        }
+    }
+    blocks.extend(columns);
+
+    let mut text = String::new();
+    let mut chars = 0;
+    let mut ordered = Vec::new();
+    for block in blocks.into_iter().filter(|block| !block.is_empty()) {
+        let mut previous: Option<(f32, f32)> = None;
+        for line in block {
+            let size = line.font_size();
+            let separator = match previous {
+                None if text.is_empty() => "",
+                Some((baseline, size)) if baseline - line.baseline <= size * PARAGRAPH_GAP_EM => {
+                    "\n"
+                }
+                _ => "\n\n",
+            };
+            text.push_str(separator);
+            chars += separator.len();
+            previous = Some((line.baseline, size));
+
+            let mut last_x1: Option<f32> = None;
+            for mut span in line.spans {
+                if last_x1.is_some_and(|x1| span.bbox.x0 - x1 > span.font_size * WORD_GAP_EM)
+                    && !text.ends_with(' ')
+                    && !span.text.starts_with(' ')
+                {
+                    text.push(' ');
+                    chars += 1;
+                }
+                last_x1 = Some(span.bbox.x1);
+                span.char_start = chars;
+                chars += span.text.chars().count();
+                span.char_end = chars;
+                text.push_str(&span.text);
+                ordered.push(span);
+            }
+        }
+    }
+
+    (text, ordered)
+}
+
+const WORD_GAP_EM: f32 = 0.15;
+const PARAGRAPH_GAP_EM: f32 = 1.8;
+const MIN_GUTTER_EM: f32 = 1.5;
+const MIN_COLUMN_SHARE: f32 = 0.2;
+/// Share of lines allowed to cross a gutter before it stops counting as one.
+const GUTTER_CROSSING_SHARE: f32 = 0.25;
+/// 200 inches, the largest page dimension PDF allows, in points.
+const MAX_PAGE_EXTENT: f32 = 14_400.0;
+
+#[derive(Debug, Clone)]
+struct Line {
+    baseline: f32,
+    spans: Vec<TextSpan>,
+}
+
+impl Line {
+    fn font_size(&self) -> f32 {
+        self.spans
+            .iter()
+            .map(|span| span.font_size)
+            .fold(0.0, f32::max)
+    }
+}
+
+fn group_lines(mut spans: Vec<TextSpan>) -> Vec<Line> {
+    spans.retain(|span| !span.text.trim().is_empty());
+    spans.sort_by(|a, b| baseline(b).total_cmp(&baseline(a)));
+
+    let mut lines = Vec::<Line>::new();
+    for span in spans {
+        match lines.last_mut() {
+            Some(line) if line.baseline - baseline(&span) <= span.font_size.max(1.0) * 0.3 => {
+                line.spans.push(span)
+            }
+            _ => lines.push(Line {
+                baseline: baseline(&span),
+                spans: vec![span],
+            }),
+        }
+    }
+    for line in &mut lines {
+        line.spans.sort_by(|a, b| a.bbox.x0.total_cmp(&b.bbox.x0));
+    }
+    lines
+}
+
+/// Baselines sit a fifth of the font size above the bottom of the span box.
+fn baseline(span: &TextSpan) -> f32 {
+    span.bbox.y0 + span.font_size * DESCENT_EM
+}
+
+/// Midpoints of vertical strips that few lines draw into and that have a
+/// reasonably wide column of text on both sides.
+fn find_gutters(lines: &[Line]) -> Vec<f32> {
+    let Some((left, right)) = lines
+        .iter()
+        .flat_map(|line| &line.spans)
+        .map(|span| (span.bbox.x0, span.bbox.x1))
+        .reduce(|(x0, x1), (a, b)| (x0.min(a), x1.max(b)))
+    else {
+        return Vec::new();
+    };
+    // Spans outside the largest page PDF allows come from bogus matrices; such
+    // pages are read as a single column rather than binned point by point.
+    let extent = right - left;
+    if !(extent > 0.0 && extent <= MAX_PAGE_EXTENT) {
+        return Vec::new();
+    }
+    let width = extent.ceil() as usize;
+
+    let mut coverage = vec![0usize; width];
+    for line in lines {
+        let mut covered = vec![false; width];
+        for span in &line.spans {
+            let start = ((span.bbox.x0 - left).floor().max(0.0) as usize).min(width);
+            let end = ((span.bbox.x1 - left).ceil().max(0.0) as usize).min(width);
+            covered[start..end].fill(true);
+        }
+        for (bin, covered) in coverage.iter_mut().zip(covered) {
+            *bin += usize::from(covered);
+        }
+    }
+
+    let mut sizes = lines.iter().map(Line::font_size).collect::<Vec<_>>();
+    sizes.sort_by(f32::total_cmp);
+    let min_gutter = sizes[sizes.len() / 2] * MIN_GUTTER_EM;
+    let min_column = width as f32 * MIN_COLUMN_SHARE;
+    let allowed = (lines.len() as f32 * GUTTER_CROSSING_SHARE) as usize;
+
+    let mut gutters = Vec::new();
+    let mut column_start = 0;
+    let mut bin = 0;
+    while bin < width {
+        if coverage[bin] > allowed {
+            bin += 1;
+            continue;
+        }
+        let start = bin;
+        while bin < width && coverage[bin] <= allowed {
+            bin += 1;
+        }
+        let strip = (start - column_start) as f32 >= min_column
+            && (width - bin) as f32 >= min_column
+            && (bin - start) as f32 >= min_gutter;
+        if strip {
+            gutters.push(left + (start + bin) as f32 / 2.0);
+            column_start = bin;
+        }
+    }
+    gutters
+}
+
+const DESCENT_EM: f32 = 0.2;
+const ASCENT_EM: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Matrix([f32; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn translate(x: f32, y: f32) -> Self {
+        Matrix([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    fn from_operands(operands: &[Object]) -> Self {
+        let mut values = Self::IDENTITY.0;
+        if operands.len() == 6 {
+            for (value, operand) in values.iter_mut().zip(operands) {
+                *value = operand.as_float().unwrap_or(*value);
+            }
+        }
+        Matrix(values)
+    }
+
+    /// `self` followed by `other`, i.e. the PDF product `self × other`.
+    fn then(self, other: Matrix) -> Matrix {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        Matrix([
+            a1 * a2 + b1 * c2,
+            a1 * b2 + b1 * d2,
+            c1 * a2 + d1 * c2,
+            c1 * b2 + d1 * d2,
+            e1 * a2 + f1 * c2 + e2,
+            e1 * b2 + f1 * d2 + f2,
+        ])
+    }
+
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GraphicsState {
+    ctm: Matrix,
+    font: Option<Vec<u8>>,
+    font_size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+}
+
+impl Default for GraphicsState {
+    fn default() -> Self {
+        Self {
+            ctm: Matrix::IDENTITY,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FontMetrics {
+    encoding: String,
+    two_byte: bool,
+    first_char: i64,
+    /// Glyph widths in thousandths of an em, from `/Widths`.
+    widths: Vec<f32>,
+    default_width: f32,
+}
+
+impl FontMetrics {
+    fn of(document: &Document, font: &Dictionary) -> Self {
+        let name = |key: &[u8]| {
+            font.get(key)
+                .and_then(Object::as_name)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .unwrap_or_default()
+        };
+        let widths = font
+            .get_deref(b"Widths", document)
+            .and_then(Object::as_array)
+            .map(|widths| {
+                widths
+                    .iter()
+                    .map(|width| {
+                        document
+                            .dereference(width)
+                            .and_then(|(_, width)| width.as_float())
+                            .unwrap_or(0.0)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let missing_width = font
+            .get_deref(b"FontDescriptor", document)
+            .and_then(Object::as_dict)
+            .and_then(|descriptor| descriptor.get(b"MissingWidth"))
+            .and_then(Object::as_float)
+            .ok();
+
+        Self {
+            encoding: font.get_font_encoding().to_string(),
+            two_byte: name(b"Subtype") == "Type0",
+            first_char: font.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0),
+            widths,
+            default_width: missing_width.filter(|width| *width > 0.0).unwrap_or(
+                if name(b"BaseFont").starts_with("Courier") {
+                    600.0
+                } else {
+                    500.0
+                },
+            ),
+        }
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        usize::try_from(i64::from(code) - self.first_char)
+            .ok()
+            .and_then(|index| self.widths.get(index))
+            .copied()
+            .filter(|width| *width > 0.0)
+            .unwrap_or(self.default_width)
+    }
+}
+
+/// TJ adjustments larger than this (thousandths of an em) read as a space.
+const TJ_SPACE_THRESHOLD: f32 = 250.0;
+
+fn show_text(
+    state: &GraphicsState,
+    fonts: &BTreeMap<Vec<u8>, FontMetrics>,
+    text_matrix: &mut Matrix,
+    shown: &[Object],
+) -> Option<TextSpan> {
+    let font = state.font.as_ref().and_then(|name| fonts.get(name));
+    let start = *text_matrix;
+    let mut text = String::new();
+    let mut advance = 0.0;
+
+    for item in shown {
+        match item {
+            Object::String(bytes, _) => {
+                text.push_str(&Document::decode_text(
+                    font.map(|font| font.encoding.as_str()),
+                    bytes,
+                ));
+                let two_byte = font.is_some_and(|font| font.two_byte);
+                let codes: Vec<u32> = if two_byte {
+                    bytes
+                        .chunks(2)
+                        .map(|pair| {
+                            pair.iter()
+                                .fold(0, |code, &byte| code << 8 | u32::from(byte))
+                        })
+                        .collect()
+                } else {
+                    bytes.iter().map(|&byte| u32::from(byte)).collect()
+                };
+                for code in codes {
+                    let glyph = font.map_or(500.0, |font| font.width(code)) / 1000.0;
+                    let word = if !two_byte && code == 32 {
+                        state.word_spacing
+                    } else {
+                        0.0
+                    };
+                    advance += (glyph * state.font_size + state.char_spacing + word)
+                        * state.horizontal_scale;
+                }
+            }
+            Object::Integer(_) | Object::Real(_) => {
+                let adjustment = item.as_float().unwrap_or(0.0);
+                if -adjustment > TJ_SPACE_THRESHOLD && !text.is_empty() && !text.ends_with(' ') {
+                    text.push(' ');
+                }
+                advance -= adjustment / 1000.0 * state.font_size * state.horizontal_scale;
+            }
+            _ => {}
+        }
+    }
+    *text_matrix = Matrix::translate(advance, 0.0).then(start);
+    if text.is_empty() {
+        return None;
+    }
+
+    let device = start.then(state.ctm);
+    let bottom = state.rise - DESCENT_EM * state.font_size;
+    let top = state.rise + ASCENT_EM * state.font_size;
+    let corners = [(0.0, bottom), (advance, bottom), (0.0, top), (advance, top)]
+        .map(|(x, y)| device.apply(x, y));
+    let bbox = corners
+        .iter()
+        .map(|&(x, y)| BoundingBox {
+            x0: x,
+            y0: y,
+            x1: x,
+            y1: y,
+        })
+        .reduce(BoundingBox::union)?;
+    let [_, _, c, d, _, _] = device.0;
+
+    Some(TextSpan {
+        text,
+        bbox,
+        font_size: state.font_size * c.hypot(d),
+        char_start: 0,
+        char_end: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{layout_text, LayoutExtractor, TextSpan};
+    use crate::test_pdf::build_positioned_pdf;
+    use crate::{BoundingBox, IngestError, PdfExtractor};
+    use tempfile::tempdir;
+
+    fn span(text: &str, x0: f32, baseline: f32) -> TextSpan {
+        TextSpan {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x0,
+                y0: baseline - 2.0,
+                x1: x0 + text.len() as f32 * 5.0,
+                y1: baseline + 8.0,
+            },
+            font_size: 10.0,
+            char_start: 0,
+            char_end: 0,
+        }
+    }
+
+    #[test]
+    fn two_column_pages_are_read_column_by_column() -> Result<(), IngestError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("two-column.pdf");
+        let mut lines = vec![(72, 740, "Hydraulic pump maintenance manual, revision 3")];
+        for row in 0..6 {
+            lines.push((72, 700 - 14 * row, "Left column line text."));
+            lines.push((320, 700 - 14 * row, "Right column line text."));
+        }
+        lines.push((72, 100, "Page 1 of 12 - issued by the maintenance office"));
+        build_positioned_pdf(&[&lines]).save(&path)?;
+
+        let pages = LayoutExtractor::default().extract_pages(&path)?;
+        let page = &pages[0];
+
+        let expected = [
+            "Hydraulic pump maintenance manual, revision 3".to_string(),
+            ["Left column line text."; 6].join("\n"),
+            ["Right column line text."; 6].join("\n"),
+            "Page 1 of 12 - issued by the maintenance office".to_string(),
+        ]
+        .join("\n\n");
+        assert_eq!(page.text, expected);
+
+        let right = page
+            .spans
+            .iter()
+            .find(|span| span.text.starts_with("Right"))
+            .expect("right column span");
+        let located = page
+            .text
+            .chars()
+            .skip(right.char_start)
+            .take(right.char_end - right.char_start)
+            .collect::<String>();
+        assert_eq!(located, right.text);
+        assert_eq!(right.char_start, expected.find("Right").expect("in text"));
+        // Courier glyphs are 0.6 em wide: 23 characters at 12 pt.
+        assert!((right.bbox.x0 - 320.0).abs() < 0.01);
+        assert!((right.bbox.x1 - (320.0 + 23.0 * 7.2)).abs() < 0.01);
+        assert!(right.bbox.y0 < 700.0 && right.bbox.y1 > 700.0);
+        Ok(())
+    }
+
+    #[test]
+    fn words_and_paragraphs_are_separated_by_their_gaps() {
+        let (text, spans) = layout_text(vec![
+            span("set to 210 bar.", 72.0, 688.0),
+            span("valve", 105.0, 700.0),
+            span("Relief", 72.0, 700.0),
+            span("Check monthly.", 72.0, 650.0),
+        ]);
+
+        assert_eq!(text, "Relief valve\nset to 210 bar.\n\nCheck monthly.");
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| (span.char_start, span.char_end))
+                .collect::<Vec<_>>(),
+            vec![(0, 6), (7, 12), (13, 28), (30, 44)]
+        );
+    }
+
+    #[test]
+    fn spans_at_absurd_coordinates_are_read_without_columns() {
+        let mut spans = vec![span("Relief valve", 72.0, 700.0)];
+        spans.push(span("stray", 1e20, 688.0));
+        spans.push(span("Check monthly.", 72.0, 676.0));
+
+        let (text, spans) = layout_text(spans);
+        assert_eq!(text, "Relief valve\nstray\nCheck monthly.");
+        assert_eq!(spans.len(), 3);
+    }
+}
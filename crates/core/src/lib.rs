@@ -1,26 +1,73 @@
+pub mod archives;
+pub mod chain;
 pub mod chunking;
+pub mod discovery;
+pub mod diversity;
 pub mod embeddings;
 pub mod error;
 pub mod extractor;
+pub mod fusion;
+pub mod highlight;
 pub mod ingest;
+pub mod layout;
+pub mod llm_ocr;
 pub mod models;
+pub mod ocr_cache;
 pub mod orchestrator;
+pub mod pagination;
+pub mod passwords;
+pub mod query;
+pub mod rerank;
+pub mod sources;
 pub mod store;
 pub mod stores;
+pub mod tesseract;
+#[cfg(test)]
+mod test_pdf;
 pub mod traits;
+pub mod watch;
 
+pub use archives::{
+    container_kind, expand_container, resolve_source_path, ContainerKind, ContainerMember,
+    ExpandedContainer, ResolvedSource,
+};
+pub use chain::{ExtractorChain, ExtractorStep, PageAcceptance};
 pub use chunking::{build_chunks, chunk_by_paragraph, normalize_whitespace, ChunkingConfig};
+pub use discovery::{
+    discover_files, DiscoveredFiles, DiscoveryPolicy, UnreadablePath, IGNORE_FILE_NAME,
+};
+pub use diversity::{collapse_by_document, mmr, DIVERSITY_POOL_FACTOR};
 pub use embeddings::{CharacterNgramEmbedder, Embedder, DEFAULT_EMBEDDING_DIMENSIONS};
-pub use error::{IngestError, SearchError};
-pub use extractor::{extract_page_texts, PageText, PdfExtractor};
+pub use error::{FailureCategory, IngestError, SearchError};
+pub use extractor::{extract_page_texts, ExtractionMethod, LopdfExtractor, PageText, PdfExtractor};
+pub use fusion::{
+    CombMnz, CombSum, FusedScore, Fusion, FusionStrategy, KeywordFirst, RankedHits,
+    ScoreNormalization, WeightedRrf,
+};
+pub use highlight::{highlight, MAX_SNIPPETS, SNIPPET_CHARS};
 pub use ingest::{
-    discover_pdf_files, ingest_folder_chunks, ingest_folder_chunks_best_effort, IngestionReport,
-    SkippedPdf,
+    discover_documents, discover_pdf_files, generate_document_id, ingest_discovered,
+    ingest_folder_chunks, ingest_folder_chunks_best_effort, FileReport, FileStatus, IndexingStatus,
+    IngestWarning, IngestionReport, SkippedPdf,
 };
+pub use layout::{layout_text, positioned_spans, LayoutExtractor, TextSpan};
+pub use llm_ocr::{LlmOcrExtractor, OcrEndpointConfig};
 pub use models::{
-    ChunkKind, DocumentFingerprint, IngestionOptions, PdfChunk, QueryFilters, SearchCandidate,
-    SearchMode, SearchQuery, SearchResult,
+    BoundingBox, ChunkContext, ChunkKind, Citation, ContextChunk, DocumentFingerprint, Explanation,
+    Highlight, IngestionOptions, ModeExplanation, ModeWeight, PageSpan, PdfChunk, QueryFilters,
+    SearchCandidate, SearchMode, SearchQuery, SearchResult,
+};
+pub use ocr_cache::{OcrCache, OcrCacheStats, PruneStats};
+pub use orchestrator::{BackendTimeouts, SearchCoordinator};
+pub use pagination::{PageCursor, MAX_PAGE_DEPTH};
+pub use passwords::PasswordMap;
+pub use query::{FilterField, QueryExpr};
+pub use rerank::{HttpReranker, LexicalReranker, Rerank, Reranker, DEFAULT_RERANK_TOP_N};
+pub use sources::{
+    DocumentFormat, DocumentSource, HtmlSource, MarkdownSource, PdfSource, SourceRegistry,
+    TextSource,
 };
-pub use orchestrator::SearchCoordinator;
 pub use stores::{Neo4jStore, OpenSearchStore, QdrantStore};
+pub use tesseract::{OcrExtractor, TesseractConfig};
 pub use traits::{GraphIndex, KeywordIndex, VectorIndex};
+pub use watch::{ChangeSet, FolderWatcher, IndexedFile, IndexedFiles, WatchBatch, WatchTarget};
@@ -0,0 +1,716 @@
+use crate::error::IngestError;
+use crate::extractor::{
+    renumber_subset_pages, subset_pdf, ExtractionMethod, PageText, PdfExtractor,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lopdf::Document;
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize)]
+struct LlmOcrRequest<'a> {
+    pdf_base64: String,
+    source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_numbers: Option<&'a [u32]>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LlmOcrResponse {
+    pages: Option<Vec<LlmOcrPage>>,
+    text: Option<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LlmOcrPage {
+    #[serde(default)]
+    page: Option<u32>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OcrEndpointConfig {
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_pages_per_request: usize,
+    pub max_payload_bytes: usize,
+    pub max_concurrent_requests: usize,
+}
+
+impl OcrEndpointConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_pages_per_request: 20,
+            max_payload_bytes: 20 * 1024 * 1024,
+            max_concurrent_requests: 2,
+        }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let endpoint = env_value("LLM_OCR_ENDPOINT")?;
+        let mut config = Self::new(endpoint);
+        config.api_key = env_value("LLM_OCR_API_KEY");
+
+        if let Some(seconds) = env_number("LLM_OCR_TIMEOUT_SECS") {
+            config.timeout = Duration::from_secs(seconds);
+        }
+        if let Some(retries) = env_number("LLM_OCR_MAX_RETRIES") {
+            config.max_retries = retries;
+        }
+        if let Some(pages) = env_number("LLM_OCR_PAGES_PER_REQUEST") {
+            config.max_pages_per_request = pages;
+        }
+        if let Some(bytes) = env_number("LLM_OCR_MAX_PAYLOAD_BYTES") {
+            config.max_payload_bytes = bytes;
+        }
+        if let Some(requests) = env_number("LLM_OCR_CONCURRENCY") {
+            config.max_concurrent_requests = requests;
+        }
+
+        Some(config)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    let value = std::env::var(name).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env_value(name)?.parse().ok()
+}
+
+#[derive(Debug)]
+struct OcrBatch {
+    pdf: Vec<u8>,
+    page_numbers: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmOcrExtractor {
+    config: OcrEndpointConfig,
+}
+
+impl LlmOcrExtractor {
+    pub fn new(config: OcrEndpointConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        OcrEndpointConfig::from_env().map(Self::new)
+    }
+
+    pub fn config(&self) -> &OcrEndpointConfig {
+        &self.config
+    }
+
+    fn recognize(
+        &self,
+        pdf: &[u8],
+        path: &Path,
+        page_numbers: Option<&[u32]>,
+    ) -> Result<Vec<PageText>, IngestError> {
+        let batches = self.plan_batches(pdf, path, page_numbers)?;
+        tokio::task::block_in_place(|| self.send_batches(&batches, path))
+    }
+
+    fn fits_payload(&self, pdf_len: usize) -> bool {
+        pdf_len.div_ceil(3) * 4 <= self.config.max_payload_bytes
+    }
+
+    fn plan_batches(
+        &self,
+        pdf: &[u8],
+        path: &Path,
+        page_numbers: Option<&[u32]>,
+    ) -> Result<Vec<OcrBatch>, IngestError> {
+        let max_pages = self.config.max_pages_per_request.max(1);
+        let document = Document::load_mem(pdf).ok();
+        let page_count = match (page_numbers, &document) {
+            (Some(page_numbers), _) => page_numbers.len(),
+            (None, Some(document)) => document.get_pages().len(),
+            (None, None) => 0,
+        };
+
+        if page_count <= max_pages && self.fits_payload(pdf.len()) {
+            return Ok(vec![OcrBatch {
+                pdf: pdf.to_vec(),
+                page_numbers: page_numbers.map(<[u32]>::to_vec),
+            }]);
+        }
+
+        let Some(document) = document else {
+            return Err(IngestError::TooLarge(format!(
+                "{} is {} bytes, above the {} byte OCR payload limit, and cannot be split into pages",
+                path.display(),
+                pdf.len(),
+                self.config.max_payload_bytes
+            )));
+        };
+
+        let local_pages = document.get_pages().into_keys().collect::<Vec<_>>();
+        let mut pending = local_pages
+            .chunks(max_pages)
+            .map(<[u32]>::to_vec)
+            .collect::<VecDeque<_>>();
+        let mut batches = Vec::new();
+
+        while let Some(local) = pending.pop_front() {
+            let subset = subset_pdf(&document, &local)?;
+            if !self.fits_payload(subset.len()) {
+                if let [page] = local.as_slice() {
+                    return Err(IngestError::TooLarge(format!(
+                        "page {page} of {} is {} bytes, above the {} byte OCR payload limit",
+                        path.display(),
+                        subset.len(),
+                        self.config.max_payload_bytes
+                    )));
+                }
+                let (head, tail) = local.split_at(local.len() / 2);
+                pending.push_front(tail.to_vec());
+                pending.push_front(head.to_vec());
+                continue;
+            }
+
+            let original = local
+                .iter()
+                .map(|&number| {
+                    page_numbers
+                        .and_then(|page_numbers| page_numbers.get(number as usize - 1).copied())
+                        .unwrap_or(number)
+                })
+                .collect();
+            batches.push(OcrBatch {
+                pdf: subset,
+                page_numbers: Some(original),
+            });
+        }
+
+        Ok(batches)
+    }
+
+    fn send_batches(
+        &self,
+        batches: &[OcrBatch],
+        path: &Path,
+    ) -> Result<Vec<PageText>, IngestError> {
+        let client = Client::builder()
+            .timeout(self.config.timeout)
+            .connect_timeout(self.config.connect_timeout)
+            .build()
+            .map_err(|error| ocr_request_error(&self.config.endpoint, error))?;
+
+        let next = AtomicUsize::new(0);
+        let workers = self
+            .config
+            .max_concurrent_requests
+            .clamp(1, batches.len().max(1));
+
+        let mut results = std::thread::scope(|scope| {
+            let handles = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut sent = Vec::new();
+                        while let Some(batch) = batches.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            sent.push((
+                                batch.page_numbers.clone(),
+                                self.send_with_retries(&client, batch, path),
+                            ));
+                        }
+                        sent
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Vec<_>>()
+        });
+        results.sort_by_key(|(page_numbers, _)| {
+            page_numbers.as_ref().and_then(|p| p.first().copied())
+        });
+
+        // Pages of a batch that failed after its retries come back empty with
+        // the error as `failure`, so that the chain can report them.
+        let mut pages = Vec::new();
+        let mut failed = Vec::new();
+        for (page_numbers, result) in results {
+            match result {
+                Ok(batch_pages) => pages.extend(batch_pages),
+                Err(error) => failed.push((page_numbers, error)),
+            }
+        }
+
+        if pages.is_empty() {
+            if let Some((_, error)) = failed.into_iter().next() {
+                return Err(error);
+            }
+            return Ok(pages);
+        }
+        for (page_numbers, error) in failed {
+            let reason = error.to_string();
+            pages.extend(page_numbers.into_iter().flatten().map(|number| PageText {
+                number,
+                text: String::new(),
+                method: ExtractionMethod::Ocr,
+                confidence: None,
+                section: None,
+                spans: Vec::new(),
+                failure: Some(reason.clone()),
+            }));
+        }
+        pages.sort_by_key(|page| page.number);
+        Ok(pages)
+    }
+
+    fn send_with_retries(
+        &self,
+        client: &Client,
+        batch: &OcrBatch,
+        path: &Path,
+    ) -> Result<Vec<PageText>, IngestError> {
+        let cfg = &self.config;
+        let payload = LlmOcrRequest {
+            pdf_base64: STANDARD.encode(&batch.pdf),
+            source_path: path.to_string_lossy().to_string(),
+            page_numbers: batch.page_numbers.as_deref(),
+        };
+
+        let mut attempt = 0;
+        loop {
+            let mut request = client.post(&cfg.endpoint).json(&payload);
+            if let Some(api_key) = &cfg.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            let retry_after = match request.send() {
+                Ok(response) if response.status().is_success() => {
+                    return self.read_response(response, batch, path)
+                }
+                Ok(response) if is_retryable(response.status()) && attempt < cfg.max_retries => {
+                    retry_after(&response)
+                }
+                Ok(response) => {
+                    return Err(IngestError::OcrFailed(format!(
+                        "multimodal OCR request to {} returned {} after {} attempt(s)",
+                        cfg.endpoint,
+                        response.status(),
+                        attempt + 1
+                    )))
+                }
+                Err(error)
+                    if (error.is_timeout() || error.is_connect()) && attempt < cfg.max_retries =>
+                {
+                    None
+                }
+                Err(error) => return Err(ocr_request_error(&cfg.endpoint, error)),
+            };
+
+            std::thread::sleep(
+                retry_after
+                    .unwrap_or_else(|| cfg.backoff(attempt))
+                    .min(cfg.max_backoff),
+            );
+            attempt += 1;
+        }
+    }
+
+    fn read_response(
+        &self,
+        response: Response,
+        batch: &OcrBatch,
+        path: &Path,
+    ) -> Result<Vec<PageText>, IngestError> {
+        let payload: LlmOcrResponse = response
+            .json()
+            .map_err(|error| ocr_request_error(&self.config.endpoint, error))?;
+        let mut pages = payload_to_pages(&payload, path)?;
+        if let Some(page_numbers) = &batch.page_numbers {
+            pages = renumber_subset_pages(pages, page_numbers);
+        }
+
+        if pages.is_empty() {
+            return Err(IngestError::OcrFailed(format!(
+                "multimodal OCR response has no readable text: {}",
+                path.display()
+            )));
+        }
+
+        Ok(pages)
+    }
+}
+
+impl PdfExtractor for LlmOcrExtractor {
+    fn name(&self) -> &str {
+        "llm-ocr"
+    }
+
+    fn cache_identity(&self) -> Option<String> {
+        Some(format!("llm-ocr:{}", self.config.endpoint))
+    }
+
+    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
+        self.recognize(&fs::read(path)?, path, None)
+    }
+
+    fn extract_page_subset(
+        &self,
+        pdf: &[u8],
+        source: &Path,
+        page_numbers: &[u32],
+    ) -> Result<Vec<PageText>, IngestError> {
+        self.recognize(pdf, source, Some(page_numbers))
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn ocr_request_error(endpoint: &str, error: reqwest::Error) -> IngestError {
+    if error.is_timeout() {
+        IngestError::Timeout(format!("multimodal OCR request to {endpoint}: {error}"))
+    } else if error.is_connect() {
+        IngestError::OcrUnavailable(format!("multimodal OCR endpoint {endpoint}: {error}"))
+    } else {
+        IngestError::Http(error)
+    }
+}
+
+fn normalize_confidence(confidence: Option<f32>) -> Option<f32> {
+    confidence
+        .filter(|value| value.is_finite())
+        .map(|value| if value > 1.0 { value / 100.0 } else { value })
+        .map(|value| value.clamp(0.0, 1.0))
+}
+
+fn payload_to_pages(payload: &LlmOcrResponse, path: &Path) -> Result<Vec<PageText>, IngestError> {
+    if let Some(listed) = &payload.pages {
+        let listed = listed
+            .iter()
+            .filter_map(|page| {
+                let text = page.text.as_ref().map(|value| value.trim().to_string());
+                text.and_then(|normalized| {
+                    if normalized.is_empty() {
+                        None
+                    } else {
+                        let page_number = page.page.unwrap_or(1);
+                        Some(PageText {
+                            number: page_number,
+                            text: normalized,
+                            method: ExtractionMethod::Ocr,
+                            confidence: normalize_confidence(
+                                page.confidence.or(payload.confidence),
+                            ),
+                            section: None,
+                            spans: Vec::new(),
+                            failure: None,
+                        })
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if !listed.is_empty() {
+            return Ok(listed);
+        }
+    }
+
+    if let Some(raw_text) = &payload.text {
+        let pages = raw_text
+            .split('\u{000c}')
+            .enumerate()
+            .filter_map(|(index, chunk)| {
+                let normalized = chunk.trim().to_string();
+                if normalized.is_empty() {
+                    None
+                } else {
+                    Some(PageText {
+                        number: (index + 1) as u32,
+                        text: normalized,
+                        method: ExtractionMethod::Ocr,
+                        confidence: normalize_confidence(payload.confidence),
+                        section: None,
+                        spans: Vec::new(),
+                        failure: None,
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !pages.is_empty() {
+            return Ok(pages);
+        }
+    }
+
+    Err(IngestError::OcrFailed(format!(
+        "multimodal OCR response was empty for {}",
+        path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{payload_to_pages, LlmOcrExtractor, LlmOcrPage, LlmOcrResponse, OcrEndpointConfig};
+    use crate::extractor::{ExtractionMethod, PdfExtractor};
+    use crate::test_pdf::build_pdf;
+    use crate::FailureCategory;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn extractor(server: &Server) -> LlmOcrExtractor {
+        LlmOcrExtractor::new(OcrEndpointConfig {
+            initial_backoff: Duration::from_millis(1),
+            ..OcrEndpointConfig::new(format!("{}/ocr", server.url()))
+        })
+    }
+
+    fn pdf_bytes(pages: &[&str]) -> Vec<u8> {
+        let mut pdf = Vec::new();
+        build_pdf(pages)
+            .save_to(&mut pdf)
+            .expect("test pdf should serialize");
+        pdf
+    }
+
+    #[test]
+    fn throttled_requests_are_retried_with_backoff() {
+        let mut server = Server::new();
+        let throttled = server
+            .mock("POST", "/ocr")
+            .with_status(429)
+            .expect(2)
+            .create();
+        let recovered = server
+            .mock("POST", "/ocr")
+            .with_status(200)
+            .with_body(r#"{"text": "Pump data plate"}"#)
+            .expect(1)
+            .create();
+
+        let pages = extractor(&server)
+            .extract_page_subset(&pdf_bytes(&[""]), Path::new("scan.pdf"), &[4])
+            .expect("third attempt should succeed");
+
+        throttled.assert();
+        recovered.assert();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].number, 4);
+        assert_eq!(pages[0].text, "Pump data plate");
+    }
+
+    #[test]
+    fn client_errors_and_exhausted_retries_fail_fast() {
+        let mut server = Server::new();
+        let rejected = server
+            .mock("POST", "/ocr")
+            .with_status(400)
+            .expect(1)
+            .create();
+        let error = extractor(&server)
+            .extract_page_subset(&pdf_bytes(&[""]), Path::new("scan.pdf"), &[1])
+            .map(|_| ())
+            .expect_err("400 is not retried");
+        rejected.assert();
+        assert_eq!(error.category(), FailureCategory::OcrFailed);
+
+        let mut server = Server::new();
+        let unavailable = server
+            .mock("POST", "/ocr")
+            .with_status(503)
+            .expect(4)
+            .create();
+        let error = extractor(&server)
+            .extract_page_subset(&pdf_bytes(&[""]), Path::new("scan.pdf"), &[1])
+            .map(|_| ())
+            .expect_err("retries are exhausted");
+        unavailable.assert();
+        assert!(error.to_string().contains("after 4 attempt(s)"));
+    }
+
+    #[test]
+    fn large_documents_are_split_into_page_batches() {
+        let mut server = Server::new();
+        let batches = [vec![1, 2], vec![3, 4], vec![5]]
+            .into_iter()
+            .map(|page_numbers| {
+                server
+                    .mock("POST", "/ocr")
+                    .match_body(Matcher::PartialJson(json!({ "page_numbers": page_numbers })))
+                    .with_status(200)
+                    .with_body(r#"{"pages": [{"page": 1, "text": "first"}, {"page": 2, "text": "second"}]}"#)
+                    .expect(1)
+                    .create()
+            })
+            .collect::<Vec<_>>();
+
+        let mut ocr = extractor(&server);
+        ocr.config.max_pages_per_request = 2;
+        let pages = ocr
+            .recognize(
+                &pdf_bytes(&["", "", "", "", ""]),
+                Path::new("scan.pdf"),
+                None,
+            )
+            .expect("all batches should succeed");
+
+        for batch in &batches {
+            batch.assert();
+        }
+        assert_eq!(
+            pages.iter().map(|page| page.number).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(pages[4].text, "first");
+    }
+
+    #[test]
+    fn pages_of_a_failed_batch_are_returned_as_failures() {
+        let mut server = Server::new();
+        for (page_numbers, status) in [(vec![1, 2], 200), (vec![3, 4], 400), (vec![5], 200)] {
+            server
+                .mock("POST", "/ocr")
+                .match_body(Matcher::PartialJson(
+                    json!({ "page_numbers": page_numbers }),
+                ))
+                .with_status(status)
+                .with_body(
+                    r#"{"pages": [{"page": 1, "text": "first"}, {"page": 2, "text": "second"}]}"#,
+                )
+                .create();
+        }
+
+        let mut ocr = extractor(&server);
+        ocr.config.max_pages_per_request = 2;
+        let pages = ocr
+            .recognize(
+                &pdf_bytes(&["", "", "", "", ""]),
+                Path::new("scan.pdf"),
+                None,
+            )
+            .expect("other batches succeeded");
+
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (page.number, page.failure.is_some()))
+                .collect::<Vec<_>>(),
+            vec![(1, false), (2, false), (3, true), (4, true), (5, false)]
+        );
+        assert!(pages[2]
+            .failure
+            .as_deref()
+            .is_some_and(|failure| failure.contains("400")));
+    }
+
+    #[test]
+    fn pages_above_the_payload_limit_are_too_large() {
+        let server = Server::new();
+        let mut ocr = extractor(&server);
+        ocr.config.max_payload_bytes = 64;
+
+        let error = ocr
+            .recognize(&pdf_bytes(&["", ""]), Path::new("scan.pdf"), None)
+            .map(|_| ())
+            .expect_err("a single page exceeds 64 bytes");
+
+        assert_eq!(error.category(), FailureCategory::TooLarge);
+    }
+
+    #[test]
+    fn ocr_payload_with_pages_converts_only_nonempty_text() {
+        let response = LlmOcrResponse {
+            pages: Some(vec![
+                LlmOcrPage {
+                    page: Some(2),
+                    text: Some("  ".to_string()),
+                    confidence: None,
+                },
+                LlmOcrPage {
+                    page: Some(3),
+                    text: Some("Page 3".to_string()),
+                    confidence: Some(0.82),
+                },
+            ]),
+            text: None,
+            confidence: None,
+        };
+
+        let pages = payload_to_pages(&response, Path::new("x.pdf"))
+            .expect("multimodal response should be parsed");
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].number, 3);
+        assert_eq!(pages[0].text, "Page 3");
+        assert_eq!(pages[0].method, ExtractionMethod::Ocr);
+        assert_eq!(pages[0].confidence, Some(0.82));
+    }
+
+    #[test]
+    fn ocr_payload_fallback_text_split_by_form_feed() {
+        let response = LlmOcrResponse {
+            pages: None,
+            text: Some("First\u{000C}Second\n".to_string()),
+            confidence: Some(91.0),
+        };
+
+        let pages = payload_to_pages(&response, Path::new("x.pdf"))
+            .expect("multimodal response should be parsed");
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].number, 1);
+        assert_eq!(pages[0].text, "First");
+        assert_eq!(pages[1].number, 2);
+        assert_eq!(pages[1].text, "Second");
+        assert_eq!(pages[1].confidence, Some(0.91));
+    }
+}
@@ -1,5 +1,17 @@
+use crate::chain::ExtractorChain;
+use crate::discovery::DiscoveryPolicy;
+use crate::error::SearchError;
+use crate::extractor::ExtractionMethod;
+use crate::fusion::Fusion;
+use crate::ocr_cache::OcrCache;
+use crate::passwords::PasswordMap;
+use crate::query::QueryExpr;
+use crate::rerank::Rerank;
+use crate::sources::SourceRegistry;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentFingerprint {
@@ -36,9 +48,99 @@ pub struct PdfChunk {
     pub text_raw: String,
     pub text_normalized: String,
     pub kind: ChunkKind,
+    #[serde(default)]
+    pub extraction_method: ExtractionMethod,
     pub ocr_confidence: Option<f32>,
     pub references: Vec<String>,
     pub units: Vec<String>,
+    #[serde(default)]
+    pub page_spans: Vec<PageSpan>,
+}
+
+impl PdfChunk {
+    pub fn citation(&self) -> Citation {
+        Citation {
+            title: self.title.clone(),
+            page_start: self.page_start,
+            page_end: self.page_end,
+            section: citation_section(self.clause_id.as_deref(), &self.section_path),
+            page_spans: self.page_spans.clone(),
+        }
+    }
+}
+
+/// Page area in PDF user-space points, origin at the bottom-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x0: f32,
+    pub y0: f32,
+    pub x1: f32,
+    pub y1: f32,
+}
+
+impl BoundingBox {
+    pub fn union(self, other: BoundingBox) -> BoundingBox {
+        BoundingBox {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+}
+
+/// The part of one page a chunk was cut from, as character offsets into that
+/// page's extracted text (`char_end` exclusive).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageSpan {
+    pub page: u32,
+    pub char_start: usize,
+    pub char_end: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub title: String,
+    pub page_start: u32,
+    pub page_end: u32,
+    pub section: Option<String>,
+    #[serde(default)]
+    pub page_spans: Vec<PageSpan>,
+}
+
+impl fmt::Display for Citation {
+    /// `pump.pdf p.14–15, §5.2.3`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+        match (self.page_start, self.page_end) {
+            (0, _) => {}
+            (start, end) if end > start => write!(f, " p.{start}\u{2013}{end}")?,
+            (start, _) => write!(f, " p.{start}")?,
+        }
+        if let Some(section) = &self.section {
+            write!(f, ", {section}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `§5.2.3` from a clause heading such as `5.2.3 Relief valves`, otherwise the
+/// section path when the chunk has one.
+pub fn citation_section(clause_id: Option<&str>, section_path: &str) -> Option<String> {
+    let clause_number = clause_id.and_then(|clause| {
+        let number = clause
+            .trim()
+            .split(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+            .next()?
+            .trim_end_matches('.');
+        (!number.is_empty()).then(|| format!("\u{a7}{number}"))
+    });
+    clause_number.or_else(|| {
+        let section = section_path.trim();
+        (!section.is_empty() && section != "unassigned").then(|| section.to_string())
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
@@ -50,19 +152,80 @@ pub struct QueryFilters {
     pub path_prefix: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SearchQuery {
     pub text: String,
+    /// Page size.
     pub top_k: usize,
+    /// Hits to skip before the page starts; ignored when `cursor` is set.
+    #[serde(default)]
+    pub offset: usize,
+    /// `SearchResult::next_cursor` of the previous page.
+    #[serde(default)]
+    pub cursor: Option<String>,
     pub mandatory_terms: Vec<String>,
     pub must_not_terms: Vec<String>,
+    /// Exact phrases every hit must contain.
+    #[serde(default)]
+    pub phrases: Vec<String>,
     pub filters: QueryFilters,
     pub explain: bool,
+    #[serde(default)]
+    pub min_ocr_confidence: Option<f32>,
+    #[serde(default)]
+    pub downweight_ocr: bool,
+    #[serde(default)]
+    pub fusion: Fusion,
+    /// Backends to query and the weight each carries in fusion; modes not
+    /// listed are never called.
+    #[serde(default = "ModeWeight::defaults")]
+    pub modes: Vec<ModeWeight>,
+    /// Rescores the best fused hits before truncation to `top_k`.
+    #[serde(default)]
+    pub rerank: Option<Rerank>,
+    /// Boolean structure from `SearchQuery::parse`; hits must satisfy it.
+    #[serde(default)]
+    pub expression: Option<QueryExpr>,
+    /// Return only the best hit of each document.
+    #[serde(default)]
+    pub collapse_documents: bool,
+    /// MMR trade-off between relevance (1.0) and novelty (0.0); `None` keeps
+    /// the ranked order.
+    #[serde(default)]
+    pub diversity: Option<f64>,
+    /// Chunks of surrounding text to return on either side of each hit.
+    #[serde(default)]
+    pub context_chunks: usize,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            top_k: 10,
+            offset: 0,
+            cursor: None,
+            mandatory_terms: Vec::new(),
+            must_not_terms: Vec::new(),
+            phrases: Vec::new(),
+            filters: QueryFilters::default(),
+            explain: false,
+            min_ocr_confidence: None,
+            downweight_ocr: false,
+            fusion: Fusion::default(),
+            modes: ModeWeight::defaults(),
+            rerank: None,
+            expression: None,
+            collapse_documents: false,
+            diversity: None,
+            context_chunks: 0,
+        }
+    }
 }
 
 impl SearchQuery {
     pub fn all_terms_required(&self) -> Vec<String> {
-        if !self.mandatory_terms.is_empty() {
+        let mut required = if !self.mandatory_terms.is_empty() || self.expression.is_some() {
             self.mandatory_terms.clone()
         } else {
             self.text
@@ -70,7 +233,17 @@ impl SearchQuery {
                 .map(|token| token.to_lowercase())
                 .filter(|token| token.len() > 2)
                 .collect()
-        }
+        };
+        required.extend(self.phrases.iter().map(|phrase| phrase.to_lowercase()));
+        required
+    }
+
+    /// Fusion weight of `mode`, or `None` when the query does not use it.
+    pub fn mode_weight(&self, mode: SearchMode) -> Option<f64> {
+        self.modes
+            .iter()
+            .find(|selected| selected.mode == mode)
+            .map(|selected| selected.weight)
     }
 }
 
@@ -81,6 +254,89 @@ pub enum SearchMode {
     Graph,
 }
 
+impl SearchMode {
+    pub fn default_weight(self) -> f64 {
+        match self {
+            SearchMode::Keyword => 0.55,
+            SearchMode::Vector => 0.35,
+            SearchMode::Graph => 0.10,
+        }
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SearchMode::Keyword => "keyword",
+            SearchMode::Vector => "vector",
+            SearchMode::Graph => "graph",
+        })
+    }
+}
+
+impl FromStr for SearchMode {
+    type Err = SearchError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "keyword" => Ok(SearchMode::Keyword),
+            "vector" => Ok(SearchMode::Vector),
+            "graph" => Ok(SearchMode::Graph),
+            other => Err(SearchError::InvalidQuery(format!(
+                "unknown search mode `{other}` (expected keyword, vector or graph)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ModeWeight {
+    pub mode: SearchMode,
+    pub weight: f64,
+}
+
+impl ModeWeight {
+    /// Keyword 0.55, vector 0.35, graph expansion 0.10.
+    pub fn defaults() -> Vec<ModeWeight> {
+        [SearchMode::Keyword, SearchMode::Vector, SearchMode::Graph]
+            .into_iter()
+            .map(ModeWeight::from)
+            .collect()
+    }
+}
+
+impl From<SearchMode> for ModeWeight {
+    fn from(mode: SearchMode) -> Self {
+        Self {
+            mode,
+            weight: mode.default_weight(),
+        }
+    }
+}
+
+/// `keyword` or `keyword=0.7`.
+impl FromStr for ModeWeight {
+    type Err = SearchError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((mode, weight)) = value.split_once('=') else {
+            return value.parse::<SearchMode>().map(ModeWeight::from);
+        };
+        let weight = weight
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|weight| weight.is_finite() && *weight >= 0.0)
+            .ok_or_else(|| {
+                SearchError::InvalidQuery(format!("invalid weight in search mode `{value}`"))
+            })?;
+        Ok(Self {
+            mode: mode.parse()?,
+            weight,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchCandidate {
     pub chunk_id: String,
@@ -91,13 +347,111 @@ pub struct SearchCandidate {
     pub chunk: Option<PdfChunk>,
     pub text: Option<String>,
     pub mode: SearchMode,
+    #[serde(default)]
+    pub ocr_confidence: Option<f32>,
+    #[serde(default)]
+    pub citation: Option<Citation>,
+    /// Clause the chunk belongs to, from the stored chunk fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clause_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+    /// Further hits from the same document folded into this one by
+    /// `SearchQuery::collapse_documents`.
+    #[serde(default)]
+    pub collapsed: usize,
+    /// Chunks preceding the hit in its document, in reading order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_before: Vec<ContextChunk>,
+    /// Chunks following the hit in its document, in reading order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_after: Vec<ContextChunk>,
+    /// Short passages of the text around the query matches, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<Highlight>,
+}
+
+/// A snippet of a hit's text and the byte ranges in it that match the query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub fragment: String,
+    pub matches: Vec<(usize, usize)>,
+}
+
+/// A chunk next to a hit, returned as reading context around it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextChunk {
+    pub chunk_id: String,
+    pub chunk_index: u64,
+    pub text: String,
+}
+
+/// Neighbours of one hit, as returned by `KeywordIndex::neighbouring_chunks`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkContext {
+    pub before: Vec<ContextChunk>,
+    pub after: Vec<ContextChunk>,
+}
+
+/// Why a hit ranked where it did; attached when `SearchQuery::explain` is set.
+/// Backends may pre-fill their part (`keyword_explanation`, `graph_path`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub fusion: String,
+    pub fused_score: f64,
+    pub modes: Vec<ModeExplanation>,
+    /// Required terms the chunk text contains.
+    pub matched_terms: Vec<String>,
+    /// Excluded terms the chunk text was checked against.
+    pub excluded_terms: Vec<String>,
+    /// Filters the hit satisfied, such as `standard=ISO 4413`.
+    pub filters: Vec<String>,
+    /// Factor applied by `downweight_ocr`.
+    pub ocr_factor: Option<f32>,
+    /// Seed chunk, clauses and relationships that led graph expansion here.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub graph_path: Vec<String>,
+    /// OpenSearch's `_explanation` tree for the keyword match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword_explanation: Option<serde_json::Value>,
+    /// Score the reranker gave the hit; it replaces the fused score for ordering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeExplanation {
+    pub mode: SearchMode,
+    /// 1-based rank in that backend's result list.
+    pub rank: usize,
+    pub raw_score: f64,
+    /// Share of the fused score that came from this mode.
+    pub contribution: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub query: String,
+    /// Name of the fusion strategy that ranked `hits`.
+    #[serde(default)]
+    pub fusion: String,
     pub mode_scores: Vec<(String, usize, f64)>,
     pub hits: Vec<SearchCandidate>,
+    /// Why a backend was left out of this result, one entry per failure.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Selected modes that failed or timed out and did not contribute hits.
+    #[serde(default)]
+    pub degraded_modes: Vec<SearchMode>,
+    /// Reranker that reordered the top hits, if any.
+    #[serde(default)]
+    pub reranker: Option<String>,
+    /// Position of the first hit of this page in the full ranking.
+    #[serde(default)]
+    pub offset: usize,
+    /// Pass as `SearchQuery::cursor` to fetch the next page; `None` on the last page.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -107,6 +461,14 @@ pub struct IngestionOptions {
     pub min_chunk_chars: usize,
     pub section_heading_regex: &'static str,
     pub clause_regex: &'static str,
+    pub passwords: PasswordMap,
+    pub low_ocr_confidence: f32,
+    pub extractors: ExtractorChain,
+    pub ocr_cache: Option<OcrCache>,
+    pub sources: SourceRegistry,
+    pub expand_archives: bool,
+    pub archive_max_bytes: u64,
+    pub discovery: DiscoveryPolicy,
 }
 
 impl Default for IngestionOptions {
@@ -117,6 +479,36 @@ impl Default for IngestionOptions {
             min_chunk_chars: 120,
             section_heading_regex: r"(?m)^\s*\d+(?:\.\d+)*(?:\([a-zA-Z]\))?\s+.+$",
             clause_regex: r"(?m)^\s*\d+(?:\.\d+)*(?:\([a-zA-Z0-9]+\))?\s+[A-Za-z].+$",
+            passwords: PasswordMap::default(),
+            low_ocr_confidence: 0.6,
+            extractors: ExtractorChain::default(),
+            ocr_cache: None,
+            sources: SourceRegistry::default(),
+            expand_archives: false,
+            archive_max_bytes: 512 * 1024 * 1024,
+            discovery: DiscoveryPolicy::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SearchQuery;
+
+    #[test]
+    fn queries_serialized_before_later_options_still_deserialize() {
+        let query: SearchQuery = serde_json::from_value(serde_json::json!({
+            "text": "relief valve",
+            "top_k": 5,
+            "mandatory_terms": [],
+            "must_not_terms": [],
+            "filters": {},
+            "explain": false
+        }))
+        .expect("baseline query");
+
+        assert_eq!(query.min_ocr_confidence, None);
+        assert!(!query.downweight_ocr);
+        assert_eq!(query.modes, SearchQuery::default().modes);
+    }
+}
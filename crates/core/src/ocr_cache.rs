@@ -0,0 +1,403 @@
+use crate::error::IngestError;
+use crate::extractor::{ExtractionMethod, PageText};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const COMPLETE_MARKER: &str = "complete";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+}
+
+impl OcrCacheStats {
+    pub fn since(&self, earlier: &OcrCacheStats) -> OcrCacheStats {
+        OcrCacheStats {
+            hits: self.hits.saturating_sub(earlier.hits),
+            misses: self.misses.saturating_sub(earlier.misses),
+            writes: self.writes.saturating_sub(earlier.writes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneStats {
+    pub removed_entries: u64,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    writes: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPage {
+    backend: String,
+    page: u32,
+    text: String,
+    method: ExtractionMethod,
+    confidence: Option<f32>,
+    cached_at: DateTime<Utc>,
+}
+
+/// On-disk OCR results, one JSON file per page under
+/// `<root>/<backend hash>/<document checksum>/page-<n>.json`.
+#[derive(Debug, Clone)]
+pub struct OcrCache {
+    root: PathBuf,
+    counters: Arc<Counters>,
+}
+
+impl OcrCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            counters: Arc::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn stats(&self) -> OcrCacheStats {
+        OcrCacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            writes: self.counters.writes.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn get(&self, checksum: &str, backend: &str, page: u32) -> Option<PageText> {
+        let cached = read_entry(&self.page_path(checksum, backend, page))
+            .filter(|cached| cached.backend == backend && cached.page == page);
+        self.count(if cached.is_some() {
+            &self.counters.hits
+        } else {
+            &self.counters.misses
+        });
+        cached.map(into_page_text)
+    }
+
+    /// Pages of a document whose page list could only be learned from the
+    /// backend itself, stored with `put_document`.
+    pub fn get_document(&self, checksum: &str, backend: &str) -> Option<Vec<PageText>> {
+        let dir = self.document_dir(checksum, backend);
+        let expected = fs::read_to_string(dir.join(COMPLETE_MARKER))
+            .ok()
+            .and_then(|marker| marker.trim().parse::<usize>().ok());
+        let pages = expected.and_then(|expected| {
+            let mut pages = fs::read_dir(&dir)
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| read_entry(&entry.path()))
+                .filter(|cached| cached.backend == backend)
+                .map(into_page_text)
+                .collect::<Vec<_>>();
+            pages.sort_by_key(|page| page.number);
+            // Pages removed since the marker was written make the set partial.
+            (pages.len() == expected).then_some(pages)
+        });
+
+        self.count(if pages.is_some() {
+            &self.counters.hits
+        } else {
+            &self.counters.misses
+        });
+        pages
+    }
+
+    pub fn put(
+        &self,
+        checksum: &str,
+        backend: &str,
+        pages: &[PageText],
+    ) -> Result<(), IngestError> {
+        let dir = self.document_dir(checksum, backend);
+        fs::create_dir_all(&dir)?;
+
+        for page in pages {
+            let entry = CachedPage {
+                backend: backend.to_string(),
+                page: page.number,
+                text: page.text.clone(),
+                method: page.method,
+                confidence: page.confidence,
+                cached_at: Utc::now(),
+            };
+            let path = self.page_path(checksum, backend, page.number);
+            let staging = path.with_extension("json.tmp");
+            fs::write(&staging, serde_json::to_vec(&entry)?)?;
+            fs::rename(&staging, &path)?;
+            self.count(&self.counters.writes);
+        }
+
+        Ok(())
+    }
+
+    pub fn put_document(
+        &self,
+        checksum: &str,
+        backend: &str,
+        pages: &[PageText],
+    ) -> Result<(), IngestError> {
+        self.put(checksum, backend, pages)?;
+        fs::write(
+            self.document_dir(checksum, backend).join(COMPLETE_MARKER),
+            pages.len().to_string(),
+        )?;
+        Ok(())
+    }
+
+    /// Removes entries last written before `older_than` ago, or every entry
+    /// when `older_than` is `None`. Only files laid out like cache entries
+    /// are touched, so a mistyped root never loses unrelated files.
+    pub fn prune(&self, older_than: Option<Duration>) -> Result<PruneStats, IngestError> {
+        let mut stats = PruneStats::default();
+        if !self.root.is_dir() {
+            return Ok(stats);
+        }
+
+        let cutoff = older_than.and_then(|age| SystemTime::now().checked_sub(age));
+        for backend_dir in cache_dirs(&self.root, is_backend_hash)? {
+            for document_dir in cache_dirs(&backend_dir, is_checksum)? {
+                let mut pruned_pages = false;
+                let mut marker = None;
+                for entry in fs::read_dir(&document_dir)? {
+                    let entry = entry?;
+                    let name = entry.file_name();
+                    let Some(name) = name.to_str() else {
+                        continue;
+                    };
+                    if !entry.file_type()?.is_file() {
+                        continue;
+                    }
+                    if name == COMPLETE_MARKER {
+                        marker = Some(entry);
+                        continue;
+                    }
+                    if !is_page_entry(name) {
+                        continue;
+                    }
+
+                    let metadata = entry.metadata()?;
+                    if is_expired(&metadata, cutoff) {
+                        fs::remove_file(entry.path())?;
+                        stats.removed_entries += 1;
+                        stats.freed_bytes += metadata.len();
+                        pruned_pages = true;
+                    }
+                }
+
+                // A marker must not outlive any of the pages it vouches for.
+                if let Some(marker) = marker {
+                    let metadata = marker.metadata()?;
+                    if pruned_pages || is_expired(&metadata, cutoff) {
+                        fs::remove_file(marker.path())?;
+                        stats.freed_bytes += metadata.len();
+                    }
+                }
+                let _ = fs::remove_dir(&document_dir);
+            }
+            let _ = fs::remove_dir(&backend_dir);
+        }
+
+        Ok(stats)
+    }
+
+    fn document_dir(&self, checksum: &str, backend: &str) -> PathBuf {
+        let backend_hash = format!("{:x}", Sha256::digest(backend.as_bytes()));
+        self.root.join(&backend_hash[..16]).join(checksum)
+    }
+
+    fn page_path(&self, checksum: &str, backend: &str, page: u32) -> PathBuf {
+        self.document_dir(checksum, backend)
+            .join(format!("page-{page}.json"))
+    }
+
+    fn count(&self, counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Subdirectories of `dir` whose names pass `is_cache_name`.
+fn cache_dirs(dir: &Path, is_cache_name: fn(&str) -> bool) -> Result<Vec<PathBuf>, IngestError> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && entry.file_name().to_str().is_some_and(is_cache_name) {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+fn is_backend_hash(name: &str) -> bool {
+    name.len() == 16
+        && name
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn is_checksum(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn is_page_entry(name: &str) -> bool {
+    name.strip_prefix("page-")
+        .and_then(|rest| {
+            rest.strip_suffix(".json")
+                .or_else(|| rest.strip_suffix(".json.tmp"))
+        })
+        .is_some_and(|number| {
+            !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit())
+        })
+}
+
+fn is_expired(metadata: &fs::Metadata, cutoff: Option<SystemTime>) -> bool {
+    match (cutoff, metadata.modified()) {
+        (Some(cutoff), Ok(modified)) => modified < cutoff,
+        _ => true,
+    }
+}
+
+fn read_entry(path: &Path) -> Option<CachedPage> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        return None;
+    }
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+fn into_page_text(cached: CachedPage) -> PageText {
+    PageText {
+        number: cached.page,
+        text: cached.text,
+        method: cached.method,
+        confidence: cached.confidence,
+        section: None,
+        spans: Vec::new(),
+        failure: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OcrCache, OcrCacheStats};
+    use crate::extractor::{ExtractionMethod, PageText};
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn ocr_page(number: u32, text: &str) -> PageText {
+        PageText {
+            number,
+            text: text.to_string(),
+            method: ExtractionMethod::Ocr,
+            confidence: Some(0.8),
+            section: None,
+            spans: Vec::new(),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn entries_are_keyed_by_checksum_page_and_backend() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let cache = OcrCache::new(dir.path());
+        cache.put("abc", "tesseract:eng:300dpi", &[ocr_page(2, "Data plate")])?;
+
+        let hit = cache
+            .get("abc", "tesseract:eng:300dpi", 2)
+            .expect("same key should hit");
+        assert_eq!(hit.text, "Data plate");
+        assert_eq!(hit.confidence, Some(0.8));
+        assert!(cache.get("abc", "tesseract:eng:300dpi", 3).is_none());
+        assert!(cache.get("abd", "tesseract:eng:300dpi", 2).is_none());
+        assert!(cache.get("abc", "tesseract:deu:300dpi", 2).is_none());
+        assert_eq!(
+            cache.stats(),
+            OcrCacheStats {
+                hits: 1,
+                misses: 3,
+                writes: 1
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prune_removes_expired_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let cache = OcrCache::new(dir.path().join("ocr-cache"));
+        cache.put_document("abc", "llm-ocr", &[ocr_page(1, "a"), ocr_page(2, "b")])?;
+        assert_eq!(
+            cache
+                .get_document("abc", "llm-ocr")
+                .map(|pages| pages.len()),
+            Some(2)
+        );
+
+        let kept = cache.prune(Some(Duration::from_secs(3600)))?;
+        assert_eq!(kept.removed_entries, 0);
+        assert!(cache.get("abc", "llm-ocr", 1).is_some());
+
+        let pruned = cache.prune(None)?;
+        assert_eq!(pruned.removed_entries, 2);
+        assert!(pruned.freed_bytes > 0);
+        assert!(cache.get_document("abc", "llm-ocr").is_none());
+        assert!(cache.root().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn prune_leaves_files_outside_the_cache_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let cache = OcrCache::new(dir.path());
+        cache.put_document("abc", "llm-ocr", &[ocr_page(1, "a")])?;
+        let document_dir = cache.document_dir("abc", "llm-ocr");
+        let backend_dir = document_dir.parent().expect("backend dir").to_path_buf();
+        let foreign = [
+            dir.path().join("notes.txt"),
+            dir.path().join("photos").join("page-1.json"),
+            backend_dir.join("readme.txt"),
+            document_dir.join("thesis.pdf"),
+        ];
+        for path in &foreign {
+            fs::create_dir_all(path.parent().expect("parent"))?;
+            fs::write(path, b"keep me")?;
+        }
+
+        let pruned = cache.prune(None)?;
+
+        assert_eq!(pruned.removed_entries, 1);
+        for path in &foreign {
+            assert!(path.exists(), "{} was deleted", path.display());
+        }
+        assert!(!document_dir.join("page-1.json").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn partial_documents_are_not_served_as_complete() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let cache = OcrCache::new(dir.path());
+        cache.put_document("abc", "llm-ocr", &[ocr_page(1, "a"), ocr_page(2, "b")])?;
+
+        fs::remove_file(cache.page_path("abc", "llm-ocr", 2))?;
+
+        assert!(cache.get_document("abc", "llm-ocr").is_none());
+        assert!(cache.get("abc", "llm-ocr", 1).is_some());
+        Ok(())
+    }
+}
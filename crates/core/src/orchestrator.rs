@@ -1,7 +1,35 @@
+use crate::diversity::{collapse_by_document, mmr, DIVERSITY_POOL_FACTOR};
 use crate::embeddings::{CharacterNgramEmbedder, Embedder};
+use crate::fusion::{FusionStrategy, RankedHits};
+use crate::highlight::highlight;
+use crate::pagination::{PageCursor, MAX_PAGE_DEPTH};
+use crate::rerank::Reranker;
 use crate::traits::{GraphIndex, KeywordIndex, VectorIndex};
-use crate::{SearchCandidate, SearchError, SearchMode, SearchQuery, SearchResult};
+use crate::{
+    Citation, Explanation, Highlight, ModeExplanation, SearchCandidate, SearchError, SearchMode,
+    SearchQuery, SearchResult,
+};
 use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+/// How long each backend may take before the search continues without it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendTimeouts {
+    pub keyword: Duration,
+    pub vector: Duration,
+    pub graph: Duration,
+}
+
+impl Default for BackendTimeouts {
+    fn default() -> Self {
+        Self {
+            keyword: Duration::from_secs(10),
+            vector: Duration::from_secs(10),
+            graph: Duration::from_secs(5),
+        }
+    }
+}
 
 pub struct SearchCoordinator<K, V, G>
 where
@@ -13,6 +41,7 @@ where
     vector: V,
     graph: G,
     embedder: CharacterNgramEmbedder,
+    timeouts: BackendTimeouts,
 }
 
 impl<K, V, G> SearchCoordinator<K, V, G>
@@ -27,68 +56,266 @@ where
             vector,
             graph,
             embedder: CharacterNgramEmbedder::default(),
+            timeouts: BackendTimeouts::default(),
         }
     }
 
+    pub fn with_timeouts(mut self, timeouts: BackendTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
     pub async fn search(&self, query: &SearchQuery) -> Result<SearchResult, SearchError> {
+        self.search_with_fusion(query, &query.fusion).await
+    }
+
+    /// Like `search`, but ranks with `fusion` instead of `query.fusion`.
+    pub async fn search_with_fusion(
+        &self,
+        query: &SearchQuery,
+        fusion: &dyn FusionStrategy,
+    ) -> Result<SearchResult, SearchError> {
+        let rerank = query
+            .rerank
+            .as_ref()
+            .map(|rerank| (rerank as &dyn Reranker, rerank.top_n()));
+        self.search_ranked(query, fusion, rerank).await
+    }
+
+    /// Like `search`, but rescores the best `top_n` fused hits with
+    /// `reranker` instead of `query.rerank`.
+    pub async fn search_with_reranker(
+        &self,
+        query: &SearchQuery,
+        reranker: &dyn Reranker,
+        top_n: usize,
+    ) -> Result<SearchResult, SearchError> {
+        self.search_ranked(query, &query.fusion, Some((reranker, top_n)))
+            .await
+    }
+
+    async fn search_ranked(
+        &self,
+        query: &SearchQuery,
+        fusion: &dyn FusionStrategy,
+        rerank: Option<(&dyn Reranker, usize)>,
+    ) -> Result<SearchResult, SearchError> {
         if query.text.trim().is_empty() {
             return Err(SearchError::Request("query is empty".to_string()));
         }
 
+        let keyword_weight = query.mode_weight(SearchMode::Keyword);
+        let vector_weight = query.mode_weight(SearchMode::Vector);
+        let graph_weight = query.mode_weight(SearchMode::Graph);
+        if keyword_weight.is_none() && vector_weight.is_none() {
+            return Err(SearchError::InvalidQuery(
+                "select keyword or vector search; graph expansion starts from their hits"
+                    .to_string(),
+            ));
+        }
+
+        if query
+            .diversity
+            .is_some_and(|lambda| !(0.0..=1.0).contains(&lambda))
+        {
+            return Err(SearchError::InvalidQuery(
+                "diversity must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(|token| PageCursor::decode(token, query))
+            .transpose()?;
+        let skip = cursor
+            .as_ref()
+            .map_or(query.offset, |cursor| cursor.position);
+        if skip.saturating_add(query.top_k) > MAX_PAGE_DEPTH {
+            return Err(SearchError::InvalidQuery(format!(
+                "pages end at most {MAX_PAGE_DEPTH} hits deep; narrow the query instead"
+            )));
+        }
+        // Every page ranks everything up to its end, plus one hit to tell
+        // whether another page follows. Collapsing and diversification drop
+        // hits, so backends are then asked for a deeper pool.
+        let page_end = skip.saturating_add(query.top_k).saturating_add(1);
+        let pool_factor = if query.collapse_documents || query.diversity.is_some() {
+            DIVERSITY_POOL_FACTOR
+        } else {
+            1
+        };
+        let pool = page_end.saturating_mul(pool_factor);
+        let retrieval = SearchQuery {
+            top_k: pool,
+            ..query.clone()
+        };
+
         let required_terms = query.all_terms_required();
-        let query_vector = self.embedder.embed(&query.text);
+        let keyword_search = async {
+            match keyword_weight {
+                Some(_) => self.keyword.search_keyword(&retrieval).await,
+                None => Ok(Vec::new()),
+            }
+        };
+        let vector_search = async {
+            match vector_weight {
+                Some(_) => {
+                    let query_vector = self.embedder.embed(&query.text);
+                    self.vector.search_vector(&query_vector, &retrieval).await
+                }
+                None => Ok(Vec::new()),
+            }
+        };
+        let (keyword_result, vector_result) = tokio::join!(
+            within(SearchMode::Keyword, self.timeouts.keyword, keyword_search),
+            within(SearchMode::Vector, self.timeouts.vector, vector_search)
+        );
+
+        // A failed backend degrades the search; only when every selected
+        // retrieval backend fails is there nothing to return.
+        let mut failures = Vec::new();
+        let mut keyword_hits = recover(SearchMode::Keyword, keyword_result, &mut failures);
+        let mut vector_hits = recover(SearchMode::Vector, vector_result, &mut failures);
+        let selected = usize::from(keyword_weight.is_some()) + usize::from(vector_weight.is_some());
+        if failures.len() == selected {
+            return Err(match failures.len() {
+                1 => failures.remove(0).1,
+                _ => SearchError::Request(format!(
+                    "all search backends failed: {}",
+                    describe_failures(&failures).join("; ")
+                )),
+            });
+        }
 
-        let (keyword_hits, vector_hits) = tokio::try_join!(
-            self.keyword.search_keyword(query),
-            self.vector.search_vector(&query_vector, query)
-        )?;
+        let mut confidences = HashMap::new();
+        if query.downweight_ocr {
+            for hit in keyword_hits.iter().chain(&vector_hits) {
+                if let Some(confidence) = hit.ocr_confidence {
+                    confidences.insert(hit.chunk_id.clone(), confidence);
+                }
+            }
+            downweight_ocr_hits(&mut keyword_hits, &confidences);
+            downweight_ocr_hits(&mut vector_hits, &confidences);
+        }
 
         let mut scored = HashMap::<String, ScoredHit>::new();
-        apply_rrf(&mut scored, &keyword_hits, 0.55);
-        apply_rrf(&mut scored, &vector_hits, 0.35);
+        merge_hits(&mut scored, SearchMode::Keyword, &keyword_hits);
+        merge_hits(&mut scored, SearchMode::Vector, &vector_hits);
 
-        let candidate_ids = scored.keys().cloned().collect::<Vec<_>>();
-        let graph_hits = self
-            .graph
-            .related_chunks(&candidate_ids)
-            .await
-            .unwrap_or_default();
-        if !graph_hits.is_empty() {
-            apply_rrf(&mut scored, &graph_hits, 0.10);
+        let mut graph_hits = match graph_weight {
+            Some(_) if !scored.is_empty() => {
+                let candidate_ids = scored.keys().cloned().collect::<Vec<_>>();
+                let graph_result = within(
+                    SearchMode::Graph,
+                    self.timeouts.graph,
+                    self.graph.related_chunks(&candidate_ids, query),
+                )
+                .await;
+                recover(SearchMode::Graph, graph_result, &mut failures)
+            }
+            _ => Vec::new(),
+        };
+        downweight_ocr_hits(&mut graph_hits, &confidences);
+        merge_hits(&mut scored, SearchMode::Graph, &graph_hits);
+
+        let ranked = [
+            (SearchMode::Keyword, keyword_weight, &keyword_hits),
+            (SearchMode::Vector, vector_weight, &vector_hits),
+            (SearchMode::Graph, graph_weight, &graph_hits),
+        ]
+        .into_iter()
+        .filter(|(mode, _, _)| !failures.iter().any(|(failed, _)| failed == mode))
+        .filter_map(|(mode, weight, hits)| {
+            weight.map(|weight| RankedHits {
+                mode,
+                weight,
+                hits: hits.as_slice(),
+            })
+        })
+        .collect::<Vec<_>>();
+        for (chunk_id, fused) in fusion.fuse(&ranked) {
+            if let Some(hit) = scored.get_mut(&chunk_id) {
+                hit.total_score = fused.score;
+                hit.explanation.fused_score = fused.score;
+                for (mode, contribution) in fused.contributions {
+                    for found in hit
+                        .explanation
+                        .modes
+                        .iter_mut()
+                        .filter(|found| found.mode == mode)
+                    {
+                        found.contribution = contribution;
+                    }
+                }
+            }
         }
 
         let mut final_hits: Vec<ScoredHit> = scored
             .into_values()
             .filter(|hit| term_check(&hit.chunk_text, &required_terms))
             .filter(|hit| !contains_any_term(&hit.chunk_text, &query.must_not_terms))
+            .filter(|hit| {
+                query
+                    .expression
+                    .as_ref()
+                    .map_or(true, |expression| expression.matches(&hit.chunk_text))
+            })
+            .filter(|hit| passes_ocr_confidence(hit.ocr_confidence, query.min_ocr_confidence))
+            .map(|mut hit| {
+                hit.explanation.ocr_factor = confidences.get(&hit.chunk_id).copied();
+                hit
+            })
             .collect();
 
-        final_hits.sort_by(|left, right| right.total_score.total_cmp(&left.total_score));
-
-        let mode_scores = vec![
-            ("keyword".to_string(), 0.55),
-            ("vector".to_string(), 0.35),
-            (
-                "graph".to_string(),
-                if graph_hits.is_empty() { 0.0 } else { 0.10 },
-            ),
-        ];
+        // Ties are broken by chunk id so that every page sees the same order.
+        final_hits.sort_by(|left, right| {
+            right
+                .total_score
+                .total_cmp(&left.total_score)
+                .then_with(|| left.chunk_id.cmp(&right.chunk_id))
+        });
+        // Chunks indexed since the previous page may rank ahead of where it
+        // ended, so the window reaches past wherever its last hit now sits.
+        let window = cursor
+            .as_ref()
+            .and_then(|cursor| {
+                final_hits
+                    .iter()
+                    .position(|hit| hit.chunk_id == cursor.after)
+            })
+            .map_or(page_end, |last| page_end.max(last + query.top_k + 2));
 
-        let mode_scores = mode_scores
-            .into_iter()
-            .map(|(mode, weight)| {
-                let top_k = if mode == "graph" { 20 } else { query.top_k };
-                (mode, top_k, weight)
+        let mode_scores = ranked
+            .iter()
+            .map(|list| {
+                let top_k = if list.mode == SearchMode::Graph {
+                    20
+                } else {
+                    query.top_k
+                };
+                let weight = if list.mode == SearchMode::Graph && list.hits.is_empty() {
+                    0.0
+                } else {
+                    list.weight
+                };
+                (list.mode.to_string(), top_k, weight)
             })
             .collect();
 
-        Ok(SearchResult {
-            query: query.text.clone(),
-            mode_scores,
-            hits: final_hits
-                .into_iter()
-                .take(query.top_k)
-                .map(|item| SearchCandidate {
+        let rerank_depth = rerank.map_or(0, |(_, top_n)| top_n);
+        let mut hits = final_hits
+            .into_iter()
+            .take(window.saturating_mul(pool_factor).max(rerank_depth))
+            .map(|mut item| {
+                let explanation = query.explain.then(|| {
+                    item.explanation.fusion = fusion.name().to_string();
+                    item.explanation.matched_terms = required_terms.clone();
+                    item.explanation.excluded_terms = query.must_not_terms.clone();
+                    item.explanation.filters = applied_filters(query, &item.modes);
+                    item.explanation
+                });
+                SearchCandidate {
                     chunk_id: item.chunk_id,
                     document_id: item.document_id,
                     source_path: item.source_path,
@@ -97,12 +324,156 @@ where
                     chunk: item.chunk,
                     text: Some(item.chunk_text),
                     mode: dominant_mode(&item.modes),
-                })
-                .collect(),
+                    ocr_confidence: item.ocr_confidence,
+                    citation: item.citation,
+                    clause_id: item.clause_id,
+                    collapsed: 0,
+                    context_before: Vec::new(),
+                    context_after: Vec::new(),
+                    highlights: item.highlights,
+                    explanation,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Collapsing and MMR compare fused scores, which share one scale;
+        // reranking only part of the list first would mix two.
+        if query.collapse_documents {
+            hits = collapse_by_document(hits);
+        }
+        if let Some(lambda) = query.diversity {
+            let embeddings = hits
+                .iter()
+                .map(|hit| self.embedder.embed(hit.text.as_deref().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            hits = mmr(hits, &embeddings, lambda, window);
+        }
+        let mut warnings = describe_failures(&failures);
+        if let Some((reranker, top_n)) = rerank {
+            let window = top_n.min(hits.len());
+            match reranker.rerank(query, &hits[..window]).await {
+                Ok(scores) if scores.len() == window => {
+                    for (hit, score) in hits.iter_mut().zip(scores) {
+                        hit.score = score;
+                        if let Some(explanation) = &mut hit.explanation {
+                            explanation.rerank_score = Some(score);
+                        }
+                    }
+                    // Stable, so hits the reranker scores equally keep their fused order.
+                    hits[..window].sort_by(|left, right| right.score.total_cmp(&left.score));
+                }
+                Ok(scores) => warnings.push(format!(
+                    "{} reranker returned {} scores for {window} hits; kept fused order",
+                    reranker.name(),
+                    scores.len()
+                )),
+                Err(error) => warnings.push(format!(
+                    "{} reranker unavailable, kept fused order: {error}",
+                    reranker.name()
+                )),
+            }
+        }
+
+        // Resume after the last hit the previous page showed; if it dropped
+        // out of the ranking, fall back to its position.
+        let start = cursor
+            .as_ref()
+            .and_then(|cursor| {
+                hits.iter()
+                    .position(|hit| hit.chunk_id == cursor.after)
+                    .map(|last| last + 1)
+            })
+            .unwrap_or(skip)
+            .min(hits.len());
+        let more = hits.len() > start + query.top_k;
+        let mut hits = hits
+            .into_iter()
+            .skip(start)
+            .take(query.top_k)
+            .collect::<Vec<_>>();
+
+        for hit in &mut hits {
+            if hit.highlights.is_empty() {
+                hit.highlights = highlight(hit.text.as_deref().unwrap_or_default(), query);
+            }
+        }
+
+        if query.context_chunks > 0 && !hits.is_empty() {
+            let chunk_ids = hits
+                .iter()
+                .map(|hit| hit.chunk_id.clone())
+                .collect::<Vec<_>>();
+            let neighbours = within(
+                SearchMode::Keyword,
+                self.timeouts.keyword,
+                self.keyword
+                    .neighbouring_chunks(&chunk_ids, query.context_chunks),
+            )
+            .await;
+            match neighbours {
+                Ok(mut neighbours) => {
+                    for hit in &mut hits {
+                        if let Some(context) = neighbours.remove(&hit.chunk_id) {
+                            hit.context_before = context.before;
+                            hit.context_after = context.after;
+                        }
+                    }
+                }
+                Err(error) => warnings.push(format!("context unavailable: {error}")),
+            }
+        }
+        let next_cursor = (more && start + hits.len() < MAX_PAGE_DEPTH)
+            .then(|| hits.last())
+            .flatten()
+            .map(|last| PageCursor::new(query, start + hits.len(), &last.chunk_id).encode());
+
+        Ok(SearchResult {
+            query: query.text.clone(),
+            fusion: fusion.name().to_string(),
+            mode_scores,
+            hits,
+            warnings,
+            degraded_modes: failures.iter().map(|(mode, _)| *mode).collect(),
+            reranker: rerank.map(|(reranker, _)| reranker.name().to_string()),
+            offset: start,
+            next_cursor,
         })
     }
 }
 
+async fn within<T>(
+    mode: SearchMode,
+    limit: Duration,
+    search: impl Future<Output = Result<T, SearchError>>,
+) -> Result<T, SearchError> {
+    tokio::time::timeout(limit, search)
+        .await
+        .unwrap_or_else(|_| {
+            Err(SearchError::Timeout(format!(
+                "{mode} search did not answer within {} ms",
+                limit.as_millis()
+            )))
+        })
+}
+
+fn recover(
+    mode: SearchMode,
+    result: Result<Vec<SearchCandidate>, SearchError>,
+    failures: &mut Vec<(SearchMode, SearchError)>,
+) -> Vec<SearchCandidate> {
+    result.unwrap_or_else(|error| {
+        failures.push((mode, error));
+        Vec::new()
+    })
+}
+
+fn describe_failures(failures: &[(SearchMode, SearchError)]) -> Vec<String> {
+    failures
+        .iter()
+        .map(|(mode, error)| format!("{mode} search unavailable: {error}"))
+        .collect()
+}
+
 #[derive(Debug)]
 struct ScoredHit {
     chunk_id: String,
@@ -113,12 +484,16 @@ struct ScoredHit {
     source: String,
     chunk: Option<crate::models::PdfChunk>,
     modes: Vec<SearchMode>,
+    ocr_confidence: Option<f32>,
+    citation: Option<Citation>,
+    clause_id: Option<String>,
+    highlights: Vec<Highlight>,
+    explanation: Explanation,
 }
 
-fn apply_rrf(target: &mut HashMap<String, ScoredHit>, hits: &[SearchCandidate], weight: f64) {
-    const K: f64 = 60.0;
+/// Collects hit metadata by chunk id; scores are assigned by fusion afterwards.
+fn merge_hits(target: &mut HashMap<String, ScoredHit>, list: SearchMode, hits: &[SearchCandidate]) {
     for (position, hit) in hits.iter().enumerate() {
-        let rank_component = 1.0 / (K + (position as f64 + 1.0));
         let text = hit.text.clone().unwrap_or_default();
         let mode = mode_from_source(&hit.source);
 
@@ -131,7 +506,27 @@ fn apply_rrf(target: &mut HashMap<String, ScoredHit>, hits: &[SearchCandidate],
             source: hit.source.clone(),
             chunk: hit.chunk.clone(),
             modes: Vec::new(),
+            ocr_confidence: None,
+            citation: None,
+            clause_id: None,
+            highlights: Vec::new(),
+            explanation: Explanation::default(),
+        });
+
+        entry.explanation.modes.push(ModeExplanation {
+            mode: list,
+            rank: position + 1,
+            raw_score: hit.score,
+            contribution: 0.0,
         });
+        if let Some(reported) = &hit.explanation {
+            if entry.explanation.graph_path.is_empty() {
+                entry.explanation.graph_path = reported.graph_path.clone();
+            }
+            if entry.explanation.keyword_explanation.is_none() {
+                entry.explanation.keyword_explanation = reported.keyword_explanation.clone();
+            }
+        }
 
         if entry.chunk_text.is_empty() {
             entry.chunk_text = text;
@@ -150,8 +545,19 @@ fn apply_rrf(target: &mut HashMap<String, ScoredHit>, hits: &[SearchCandidate],
         if entry.source_path.is_empty() {
             entry.source_path = hit.source_path.clone();
         }
+        if entry.ocr_confidence.is_none() {
+            entry.ocr_confidence = hit.ocr_confidence;
+        }
+        if entry.citation.is_none() {
+            entry.citation = hit.citation.clone();
+        }
+        if entry.clause_id.is_none() {
+            entry.clause_id = hit.clause_id.clone();
+        }
+        if entry.highlights.is_empty() {
+            entry.highlights = hit.highlights.clone();
+        }
 
-        entry.total_score += (weight * rank_component) + (hit.score * 0.01);
         if let Some(found_mode) = mode {
             if !entry.modes.contains(&found_mode) {
                 entry.modes.push(found_mode);
@@ -179,6 +585,51 @@ fn dominant_mode(modes: &[SearchMode]) -> SearchMode {
     }
 }
 
+/// Filters enforced on a hit: query filters by the keyword store, the OCR
+/// confidence floor by every store and the coordinator.
+fn applied_filters(query: &SearchQuery, modes: &[SearchMode]) -> Vec<String> {
+    let filters = &query.filters;
+    let mut applied = Vec::new();
+    if modes.contains(&SearchMode::Keyword) {
+        for (field, value) in [
+            ("standard", &filters.standard),
+            ("version", &filters.version),
+            ("section_path", &filters.section_path),
+            ("clause_id", &filters.clause_id),
+        ] {
+            if let Some(value) = value {
+                applied.push(format!("{field}={value}"));
+            }
+        }
+    }
+    if let Some(minimum) = query.min_ocr_confidence {
+        applied.push(format!("ocr_confidence>={minimum}"));
+    }
+    applied
+}
+
+/// Lowers each backend score by the chunk's OCR confidence and re-sorts the
+/// list, so every fusion strategy sees low-confidence hits further down,
+/// whether it fuses scores or ranks. Negative scores are pushed further down.
+fn downweight_ocr_hits(hits: &mut [SearchCandidate], confidences: &HashMap<String, f32>) {
+    if confidences.is_empty() {
+        return;
+    }
+    for hit in hits.iter_mut() {
+        if let Some(&confidence) = confidences.get(&hit.chunk_id) {
+            hit.score -= hit.score.abs() * (1.0 - f64::from(confidence));
+        }
+    }
+    hits.sort_by(|left, right| right.score.total_cmp(&left.score));
+}
+
+fn passes_ocr_confidence(confidence: Option<f32>, minimum: Option<f32>) -> bool {
+    match (confidence, minimum) {
+        (Some(confidence), Some(minimum)) => confidence >= minimum,
+        _ => true,
+    }
+}
+
 fn term_check(text: &str, required_terms: &[String]) -> bool {
     let lowered = text.to_lowercase();
     required_terms
@@ -198,20 +649,28 @@ mod tests {
     use super::*;
     use crate::traits::{GraphIndex, KeywordIndex, VectorIndex};
     use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[derive(Default)]
     struct FakeKeywordIndex {
         hits: Vec<SearchCandidate>,
+        calls: AtomicUsize,
+        down: bool,
+        context: HashMap<String, crate::ChunkContext>,
     }
 
     #[derive(Default)]
     struct FakeVectorIndex {
         hits: Vec<SearchCandidate>,
+        calls: AtomicUsize,
+        delay: Duration,
     }
 
     #[derive(Default)]
     struct FakeGraphIndex {
         hits: Vec<SearchCandidate>,
+        calls: AtomicUsize,
+        down: bool,
     }
 
     #[async_trait]
@@ -227,8 +686,35 @@ mod tests {
             &self,
             _query: &SearchQuery,
         ) -> Result<Vec<SearchCandidate>, SearchError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down {
+                return Err(SearchError::NotReady("opensearch".to_string()));
+            }
             Ok(self.hits.clone())
         }
+
+        async fn delete_keyword_documents(
+            &self,
+            _document_ids: &[String],
+        ) -> Result<(), SearchError> {
+            Ok(())
+        }
+
+        async fn neighbouring_chunks(
+            &self,
+            chunk_ids: &[String],
+            _window: usize,
+        ) -> Result<HashMap<String, crate::ChunkContext>, SearchError> {
+            if self.down {
+                return Err(SearchError::NotReady("opensearch".to_string()));
+            }
+            Ok(self
+                .context
+                .iter()
+                .filter(|(chunk_id, _)| chunk_ids.contains(chunk_id))
+                .map(|(chunk_id, context)| (chunk_id.clone(), context.clone()))
+                .collect())
+        }
     }
 
     #[async_trait]
@@ -246,8 +732,17 @@ mod tests {
             _query_vector: &[f32],
             _query: &SearchQuery,
         ) -> Result<Vec<SearchCandidate>, SearchError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
             Ok(self.hits.clone())
         }
+
+        async fn delete_vector_documents(
+            &self,
+            _document_ids: &[String],
+        ) -> Result<(), SearchError> {
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -262,9 +757,21 @@ mod tests {
         async fn related_chunks(
             &self,
             _chunk_ids: &[String],
+            _query: &SearchQuery,
         ) -> Result<Vec<SearchCandidate>, SearchError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down {
+                return Err(SearchError::NotReady("neo4j".to_string()));
+            }
             Ok(self.hits.clone())
         }
+
+        async fn delete_graph_documents(
+            &self,
+            _document_ids: &[String],
+        ) -> Result<(), SearchError> {
+            Ok(())
+        }
     }
 
     #[tokio::test]
@@ -279,7 +786,16 @@ mod tests {
                 chunk: None,
                 text: Some("hydraulic pump failure pressure".to_string()),
                 mode: SearchMode::Keyword,
+                ocr_confidence: None,
+                citation: None,
+                clause_id: None,
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights: Vec::new(),
+                explanation: None,
             }],
+            ..FakeKeywordIndex::default()
         };
 
         let vector_store = FakeVectorIndex {
@@ -292,7 +808,16 @@ mod tests {
                 chunk: None,
                 text: Some("hydraulic pump failure pressure".to_string()),
                 mode: SearchMode::Vector,
+                ocr_confidence: None,
+                citation: None,
+                clause_id: None,
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights: Vec::new(),
+                explanation: None,
             }],
+            ..FakeVectorIndex::default()
         };
 
         let graph_store = FakeGraphIndex {
@@ -305,7 +830,16 @@ mod tests {
                 chunk: None,
                 text: Some("other chunk".to_string()),
                 mode: SearchMode::Graph,
+                ocr_confidence: None,
+                citation: None,
+                clause_id: None,
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights: Vec::new(),
+                explanation: None,
             }],
+            ..FakeGraphIndex::default()
         };
 
         let coordinator = SearchCoordinator::new(keyword_store, vector_store, graph_store);
@@ -316,6 +850,7 @@ mod tests {
             must_not_terms: Vec::new(),
             filters: Default::default(),
             explain: false,
+            ..SearchQuery::default()
         };
 
         let result = coordinator
@@ -324,6 +859,681 @@ mod tests {
             .expect("search should succeed");
         assert_eq!(result.hits.len(), 1);
         assert_eq!(result.hits[0].chunk_id, "chunk-1");
+        assert_eq!(
+            result.hits[0].highlights,
+            vec![Highlight {
+                fragment: "hydraulic pump failure pressure".to_string(),
+                matches: vec![(0, 9), (10, 14)],
+            }]
+        );
         assert_eq!(result.hits[0].mode, SearchMode::Vector);
     }
+
+    fn keyword_hit(chunk_id: &str, text: &str, ocr_confidence: Option<f32>) -> SearchCandidate {
+        SearchCandidate {
+            chunk_id: chunk_id.to_string(),
+            document_id: "doc-1".to_string(),
+            source_path: "/tmp/doc.pdf".to_string(),
+            score: 1.0,
+            source: "opensearch".to_string(),
+            chunk: None,
+            text: Some(text.to_string()),
+            mode: SearchMode::Keyword,
+            ocr_confidence,
+            citation: None,
+            clause_id: None,
+            collapsed: 0,
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            highlights: Vec::new(),
+            explanation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn low_confidence_ocr_hits_are_filtered_or_downweighted() {
+        let keyword_store = FakeKeywordIndex {
+            hits: vec![
+                keyword_hit("scanned", "relief valve scanned sheet", Some(0.3)),
+                keyword_hit("native", "relief valve datasheet", None),
+                keyword_hit("clean-scan", "relief valve clean scan", Some(0.95)),
+            ],
+            ..FakeKeywordIndex::default()
+        };
+        let coordinator = SearchCoordinator::new(
+            keyword_store,
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+
+        let filtered = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                min_ocr_confidence: Some(0.5),
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("search should succeed");
+        let ids = filtered
+            .hits
+            .iter()
+            .map(|hit| hit.chunk_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["native", "clean-scan"]);
+
+        let downweighted = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                downweight_ocr: true,
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("search should succeed");
+        assert_eq!(downweighted.hits.len(), 3);
+        assert_eq!(downweighted.hits[2].chunk_id, "scanned");
+        assert_eq!(downweighted.hits[2].ocr_confidence, Some(0.3));
+    }
+
+    #[tokio::test]
+    async fn downweighting_lowers_hits_whose_fused_score_is_negative() {
+        let scored = |chunk_id: &str, score: f64, ocr_confidence: Option<f32>| SearchCandidate {
+            score,
+            ..keyword_hit(chunk_id, "relief valve", ocr_confidence)
+        };
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    scored("best", 9.0, None),
+                    scored("middle", 5.0, None),
+                    scored("scanned", 4.0, Some(0.5)),
+                    scored("worst", 3.0, None),
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            fusion: crate::Fusion::CombSum {
+                normalization: crate::ScoreNormalization::ZScore,
+            },
+            explain: true,
+            ..SearchQuery::default()
+        };
+        let scanned = |result: &SearchResult| {
+            result
+                .hits
+                .iter()
+                .position(|hit| hit.chunk_id == "scanned")
+                .map(|position| (position, result.hits[position].score))
+                .expect("scanned hit returned")
+        };
+
+        let plain = coordinator.search(&query).await.expect("plain search");
+        let (plain_position, plain_score) = scanned(&plain);
+        assert_eq!(plain_position, 2);
+        assert!(plain_score < 0.0);
+
+        let downweighted = coordinator
+            .search(&SearchQuery {
+                downweight_ocr: true,
+                ..query
+            })
+            .await
+            .expect("downweighted search");
+        let (position, score) = scanned(&downweighted);
+        assert_eq!(position, 3);
+        assert!(score < plain_score);
+        let explanation = downweighted.hits[position]
+            .explanation
+            .as_ref()
+            .expect("explained");
+        assert_eq!(explanation.fused_score, score);
+        assert_eq!(explanation.ocr_factor, Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn unselected_modes_are_never_queried() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![keyword_hit("clause", "5.2.3 relief valve", None)],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+
+        let result = coordinator
+            .search(&SearchQuery {
+                text: "5.2.3".to_string(),
+                mandatory_terms: vec!["5.2.3".to_string()],
+                modes: vec!["keyword=0.8".parse().expect("valid mode")],
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("search should succeed");
+
+        assert_eq!(result.hits.len(), 1);
+        assert!((result.hits[0].score - 0.8 / 61.0).abs() < 1e-12);
+        assert_eq!(coordinator.keyword.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(coordinator.vector.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(coordinator.graph.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(result.mode_scores, vec![("keyword".to_string(), 10, 0.8)]);
+
+        let graph_only = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                modes: vec![SearchMode::Graph.into()],
+                ..SearchQuery::default()
+            })
+            .await;
+        assert!(matches!(graph_only, Err(SearchError::InvalidQuery(_))));
+    }
+
+    #[tokio::test]
+    async fn explain_reports_ranks_contributions_and_graph_paths() {
+        let mut vector_hit = keyword_hit("valve", "relief valve set to 210 bar", None);
+        vector_hit.source = "qdrant".to_string();
+        vector_hit.score = 0.42;
+        let mut graph_hit = keyword_hit("linked", "see clause 5.2 for the relief valve", None);
+        graph_hit.source = "neo4j".to_string();
+        graph_hit.explanation = Some(Explanation {
+            graph_path: vec!["chunk valve".to_string(), "chunk linked".to_string()],
+            ..Explanation::default()
+        });
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    keyword_hit("pump", "pump relief valve", None),
+                    keyword_hit("valve", "relief valve set to 210 bar", None),
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex {
+                hits: vec![vector_hit],
+                ..FakeVectorIndex::default()
+            },
+            FakeGraphIndex {
+                hits: vec![graph_hit],
+                ..FakeGraphIndex::default()
+            },
+        );
+
+        let result = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                must_not_terms: vec!["pneumatic".to_string()],
+                explain: true,
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("search should succeed");
+
+        let valve = &result.hits[0];
+        assert_eq!(valve.chunk_id, "valve");
+        let explanation = valve.explanation.as_ref().expect("explained");
+        assert_eq!(explanation.fusion, "weighted_rrf");
+        assert_eq!(
+            explanation
+                .modes
+                .iter()
+                .map(|mode| (mode.mode, mode.rank, mode.raw_score))
+                .collect::<Vec<_>>(),
+            vec![(SearchMode::Keyword, 2, 1.0), (SearchMode::Vector, 1, 0.42)]
+        );
+        assert!((explanation.modes[0].contribution - 0.55 / 62.0).abs() < 1e-12);
+        let total = explanation
+            .modes
+            .iter()
+            .map(|mode| mode.contribution)
+            .sum::<f64>();
+        assert!((total - valve.score).abs() < 1e-12);
+        assert_eq!(explanation.matched_terms, vec!["relief", "valve"]);
+        assert_eq!(explanation.excluded_terms, vec!["pneumatic"]);
+
+        let linked = result
+            .hits
+            .iter()
+            .find(|hit| hit.chunk_id == "linked")
+            .and_then(|hit| hit.explanation.as_ref())
+            .expect("graph hit explained");
+        assert_eq!(linked.graph_path.len(), 2);
+        assert_eq!(linked.modes[0].mode, SearchMode::Graph);
+    }
+
+    #[tokio::test]
+    async fn failed_backends_degrade_the_result_instead_of_failing_it() {
+        let mut vector_hit = keyword_hit("valve", "relief valve set to 210 bar", None);
+        vector_hit.source = "qdrant".to_string();
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                down: true,
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex {
+                hits: vec![vector_hit],
+                ..FakeVectorIndex::default()
+            },
+            FakeGraphIndex {
+                down: true,
+                ..FakeGraphIndex::default()
+            },
+        );
+
+        let result = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("vector results are still returned");
+
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(
+            result.degraded_modes,
+            vec![SearchMode::Keyword, SearchMode::Graph]
+        );
+        assert_eq!(result.warnings.len(), 2);
+        assert!(result.warnings[0].contains("keyword search unavailable"));
+        assert_eq!(
+            result
+                .mode_scores
+                .iter()
+                .map(|(mode, _, _)| mode.as_str())
+                .collect::<Vec<_>>(),
+            vec!["vector"]
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_backends_time_out_and_all_failures_are_errors() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![keyword_hit("valve", "relief valve set to 210 bar", None)],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex {
+                delay: Duration::from_secs(5),
+                ..FakeVectorIndex::default()
+            },
+            FakeGraphIndex::default(),
+        )
+        .with_timeouts(BackendTimeouts {
+            vector: Duration::from_millis(20),
+            ..BackendTimeouts::default()
+        });
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            ..SearchQuery::default()
+        };
+
+        let result = coordinator.search(&query).await.expect("keyword hits");
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.degraded_modes, vec![SearchMode::Vector]);
+        assert!(result.warnings[0].contains("within 20 ms"));
+
+        let vector_only = SearchQuery {
+            modes: vec![SearchMode::Vector.into()],
+            ..query
+        };
+        let error = coordinator
+            .search(&vector_only)
+            .await
+            .expect_err("the only selected backend timed out");
+        assert!(matches!(error, SearchError::Timeout(_)));
+    }
+
+    struct UnavailableReranker;
+
+    #[async_trait]
+    impl Reranker for UnavailableReranker {
+        fn name(&self) -> &str {
+            "unavailable"
+        }
+
+        async fn rerank(
+            &self,
+            _query: &SearchQuery,
+            _hits: &[SearchCandidate],
+        ) -> Result<Vec<f64>, SearchError> {
+            Err(SearchError::NotReady("rerank server".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn reranking_reorders_hits_beyond_top_k_and_degrades_on_failure() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    keyword_hit("apart", "valve bodies are cast; the relief line", None),
+                    keyword_hit("near", "relief pressure valve", None),
+                    keyword_hit("phrase", "set the relief valve to 210 bar", None),
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            top_k: 2,
+            explain: true,
+            rerank: Some(crate::Rerank::Lexical { top_n: 3 }),
+            ..SearchQuery::default()
+        };
+
+        let result = coordinator.search(&query).await.expect("reranked search");
+        assert_eq!(result.reranker.as_deref(), Some("lexical"));
+        assert_eq!(
+            result
+                .hits
+                .iter()
+                .map(|hit| hit.chunk_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["phrase", "near"]
+        );
+        let explanation = result.hits[0].explanation.as_ref().expect("explained");
+        assert_eq!(explanation.rerank_score, Some(result.hits[0].score));
+        assert!(explanation.fused_score < result.hits[0].score);
+
+        let result = coordinator
+            .search_with_reranker(&query, &UnavailableReranker, 3)
+            .await
+            .expect("fused results survive a reranker outage");
+        assert_eq!(
+            result
+                .hits
+                .iter()
+                .map(|hit| hit.chunk_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["apart", "near"]
+        );
+        assert!(result.warnings[0].contains("unavailable reranker unavailable"));
+    }
+
+    /// Scores hits in order from a fixed list, like a cross-encoder whose
+    /// logits are on a different scale from fused scores.
+    struct FixedReranker(Vec<f64>);
+
+    #[async_trait]
+    impl Reranker for FixedReranker {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn rerank(
+            &self,
+            _query: &SearchQuery,
+            hits: &[SearchCandidate],
+        ) -> Result<Vec<f64>, SearchError> {
+            Ok(self.0.iter().copied().take(hits.len()).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn diversification_ranks_by_fused_scores_before_reranking() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    keyword_hit("first", "relief valve on the pump", None),
+                    keyword_hit("second", "relief valve on the tank", None),
+                    keyword_hit("third", "relief valve on the line", None),
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            top_k: 3,
+            diversity: Some(1.0),
+            ..SearchQuery::default()
+        };
+
+        // Negative logits for the two reranked hits must not sink them below
+        // the unreranked third hit.
+        let result = coordinator
+            .search_with_reranker(&query, &FixedReranker(vec![-5.0, -1.0]), 2)
+            .await
+            .expect("diversified search");
+        assert_eq!(
+            result
+                .hits
+                .iter()
+                .map(|hit| hit.chunk_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["second", "first", "third"]
+        );
+    }
+
+    #[tokio::test]
+    async fn lexical_reranking_boosts_the_clause_named_by_the_query() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    keyword_hit("other", "see 5.2 for relief valve settings", None),
+                    SearchCandidate {
+                        clause_id: Some("5.2.1".to_string()),
+                        ..keyword_hit("clause", "see 5.2 for relief valve settings", None)
+                    },
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve 5.2".to_string(),
+            top_k: 2,
+            rerank: Some(crate::Rerank::Lexical { top_n: 2 }),
+            ..SearchQuery::default()
+        };
+
+        let result = coordinator.search(&query).await.expect("reranked search");
+        assert_eq!(result.hits[0].chunk_id, "clause");
+        assert_eq!(result.hits[0].clause_id.as_deref(), Some("5.2.1"));
+    }
+
+    #[tokio::test]
+    async fn collapsed_results_fill_top_k_with_distinct_documents() {
+        let mut other_manual = keyword_hit("valve-1", "relief valve body", None);
+        other_manual.document_id = "doc-2".to_string();
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: vec![
+                    keyword_hit("pump-1", "relief valve setting", None),
+                    keyword_hit("pump-2", "relief valve spring", None),
+                    keyword_hit("pump-3", "relief valve seat", None),
+                    other_manual,
+                ],
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+
+        let result = coordinator
+            .search(&SearchQuery {
+                text: "relief valve".to_string(),
+                top_k: 2,
+                collapse_documents: true,
+                diversity: Some(0.7),
+                ..SearchQuery::default()
+            })
+            .await
+            .expect("collapsed search");
+
+        assert_eq!(
+            result
+                .hits
+                .iter()
+                .map(|hit| (hit.chunk_id.as_str(), hit.collapsed))
+                .collect::<Vec<_>>(),
+            vec![("pump-1", 2), ("valve-1", 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn cursors_page_consistently_when_new_chunks_rank_ahead() {
+        let coordinator = |ids: &[&str]| {
+            SearchCoordinator::new(
+                FakeKeywordIndex {
+                    hits: ids
+                        .iter()
+                        .map(|id| keyword_hit(id, "relief valve", None))
+                        .collect(),
+                    ..FakeKeywordIndex::default()
+                },
+                FakeVectorIndex::default(),
+                FakeGraphIndex::default(),
+            )
+        };
+        let page_ids = |result: &SearchResult| {
+            result
+                .hits
+                .iter()
+                .map(|hit| hit.chunk_id.clone())
+                .collect::<Vec<_>>()
+        };
+        let before = coordinator(&["a", "b", "c", "d", "e"]);
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            top_k: 2,
+            ..SearchQuery::default()
+        };
+
+        let first = before.search(&query).await.expect("first page");
+        assert_eq!(page_ids(&first), vec!["a", "b"]);
+        let by_offset = before
+            .search(&SearchQuery {
+                offset: 2,
+                ..query.clone()
+            })
+            .await
+            .expect("offset page");
+        assert_eq!(page_ids(&by_offset), vec!["c", "d"]);
+        assert_eq!(by_offset.offset, 2);
+
+        let after = coordinator(&["new", "a", "b", "c", "d", "e"]);
+        let second = after
+            .search(&SearchQuery {
+                cursor: first.next_cursor.clone(),
+                ..query.clone()
+            })
+            .await
+            .expect("second page");
+        assert_eq!(page_ids(&second), vec!["c", "d"]);
+        assert_eq!(second.offset, 3);
+
+        let last = after
+            .search(&SearchQuery {
+                cursor: second.next_cursor.clone(),
+                ..query.clone()
+            })
+            .await
+            .expect("last page");
+        assert_eq!(page_ids(&last), vec!["e"]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn pages_stop_at_the_maximum_depth() {
+        let coordinator = SearchCoordinator::new(
+            FakeKeywordIndex {
+                hits: (0..MAX_PAGE_DEPTH + 5)
+                    .map(|index| keyword_hit(&format!("chunk-{index:04}"), "relief valve", None))
+                    .collect(),
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex::default(),
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            top_k: 10,
+            offset: MAX_PAGE_DEPTH - 10,
+            ..SearchQuery::default()
+        };
+
+        let last = coordinator.search(&query).await.expect("deepest page");
+        assert_eq!(last.hits.len(), 10);
+        assert_eq!(last.next_cursor, None);
+
+        let error = coordinator
+            .search(&SearchQuery {
+                offset: MAX_PAGE_DEPTH - 5,
+                ..query
+            })
+            .await
+            .expect_err("page beyond the maximum depth");
+        assert!(matches!(error, SearchError::InvalidQuery(_)));
+    }
+
+    #[tokio::test]
+    async fn hits_carry_neighbouring_chunks_and_missing_context_degrades() {
+        let neighbour = |chunk_index: u64| crate::ContextChunk {
+            chunk_id: format!("pump-{chunk_index}"),
+            chunk_index,
+            text: format!("paragraph {chunk_index}"),
+        };
+        let keyword_store = FakeKeywordIndex {
+            hits: vec![keyword_hit("pump-4", "relief valve", None)],
+            context: HashMap::from([(
+                "pump-4".to_string(),
+                crate::ChunkContext {
+                    before: vec![neighbour(3)],
+                    after: vec![neighbour(5)],
+                },
+            )]),
+            ..FakeKeywordIndex::default()
+        };
+        let coordinator = SearchCoordinator::new(
+            keyword_store,
+            FakeVectorIndex {
+                hits: vec![keyword_hit("pump-4", "relief valve", None)],
+                ..FakeVectorIndex::default()
+            },
+            FakeGraphIndex::default(),
+        );
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            context_chunks: 1,
+            ..SearchQuery::default()
+        };
+
+        let result = coordinator.search(&query).await.expect("search");
+        assert_eq!(result.hits[0].context_before, vec![neighbour(3)]);
+        assert_eq!(result.hits[0].context_after, vec![neighbour(5)]);
+
+        let without_context = coordinator
+            .search(&SearchQuery {
+                context_chunks: 0,
+                ..query.clone()
+            })
+            .await
+            .expect("search");
+        assert!(without_context.hits[0].context_before.is_empty());
+
+        let degraded = SearchCoordinator::new(
+            FakeKeywordIndex {
+                down: true,
+                ..FakeKeywordIndex::default()
+            },
+            FakeVectorIndex {
+                hits: vec![keyword_hit("pump-4", "relief valve", None)],
+                ..FakeVectorIndex::default()
+            },
+            FakeGraphIndex::default(),
+        )
+        .search(&query)
+        .await
+        .expect("vector hits survive");
+        assert_eq!(degraded.hits.len(), 1);
+        assert!(degraded
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with("context unavailable")));
+    }
 }
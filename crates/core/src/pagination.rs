@@ -0,0 +1,99 @@
+use crate::{SearchError, SearchQuery};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Deepest hit a page may reach. Each page ranks everything above it, so the
+/// pool asked of the backends (up to `DIVERSITY_POOL_FACTOR` times this) stays
+/// well inside OpenSearch's default 10,000-hit result window.
+pub const MAX_PAGE_DEPTH: usize = 1_000;
+
+/// Where the previous page ended. Encoded as an opaque token that is only
+/// valid for the query it was issued for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    query: String,
+    /// Number of hits already returned on earlier pages.
+    pub position: usize,
+    /// Last chunk returned, so the next page resumes after it even if new
+    /// chunks were indexed in front of it since.
+    pub after: String,
+}
+
+impl PageCursor {
+    pub fn new(query: &SearchQuery, position: usize, after: impl Into<String>) -> Self {
+        Self {
+            query: fingerprint(query),
+            position,
+            after: after.into(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decodes `token` and checks it was issued for `query`.
+    pub fn decode(token: &str, query: &SearchQuery) -> Result<Self, SearchError> {
+        let invalid = || SearchError::InvalidQuery("malformed cursor".to_string());
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token.trim())
+            .map_err(|_| invalid())?;
+        let cursor: PageCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        if cursor.query != fingerprint(query) {
+            return Err(SearchError::InvalidQuery(
+                "cursor was issued for a different query".to_string(),
+            ));
+        }
+        Ok(cursor)
+    }
+}
+
+/// Hash of everything that decides the ranking; page size, position and
+/// context are left out so later pages may change them.
+fn fingerprint(query: &SearchQuery) -> String {
+    let ranking = SearchQuery {
+        top_k: 0,
+        offset: 0,
+        cursor: None,
+        explain: false,
+        context_chunks: 0,
+        ..query.clone()
+    };
+    let digest = Sha256::digest(serde_json::to_vec(&ranking).unwrap_or_default());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PageCursor;
+    use crate::{SearchError, SearchQuery};
+
+    #[test]
+    fn cursors_round_trip_only_for_their_own_query() {
+        let query = SearchQuery {
+            text: "relief valve".to_string(),
+            ..SearchQuery::default()
+        };
+        let token = PageCursor::new(&query, 10, "chunk-10").encode();
+
+        let next_page = SearchQuery {
+            top_k: 5,
+            cursor: Some(token.clone()),
+            ..query.clone()
+        };
+        let cursor = PageCursor::decode(&token, &next_page).expect("same query");
+        assert_eq!(cursor.position, 10);
+        assert_eq!(cursor.after, "chunk-10");
+
+        let other = SearchQuery {
+            text: "relief pressure".to_string(),
+            ..SearchQuery::default()
+        };
+        assert!(matches!(
+            PageCursor::decode(&token, &other),
+            Err(SearchError::InvalidQuery(_))
+        ));
+        assert!(PageCursor::decode("not a cursor", &query).is_err());
+    }
+}
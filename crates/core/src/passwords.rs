@@ -0,0 +1,144 @@
+use crate::error::IngestError;
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone)]
+struct PasswordEntry {
+    pattern: String,
+    matcher: GlobMatcher,
+    match_file_name: bool,
+    password: String,
+}
+
+#[derive(Clone, Default)]
+pub struct PasswordMap {
+    entries: Vec<PasswordEntry>,
+}
+
+#[derive(Deserialize)]
+struct KeyringEntry {
+    pattern: String,
+    password: String,
+}
+
+impl PasswordMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(
+        &mut self,
+        pattern: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Result<(), IngestError> {
+        let pattern = pattern.into();
+        let matcher = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|error| {
+                IngestError::InvalidArgument(format!("invalid password glob {pattern}: {error}"))
+            })?
+            .compile_matcher();
+
+        self.entries.push(PasswordEntry {
+            match_file_name: !pattern.contains('/'),
+            pattern,
+            matcher,
+            password: password.into(),
+        });
+        Ok(())
+    }
+
+    pub fn from_keyring_file(path: &Path) -> Result<Self, IngestError> {
+        let entries: Vec<KeyringEntry> = serde_json::from_slice(&fs::read(path)?)?;
+        let mut map = Self::new();
+        for entry in entries {
+            map.insert(entry.pattern, entry.password)?;
+        }
+        Ok(map)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn passwords_for(&self, path: &Path) -> Vec<&str> {
+        let file_name = path.file_name().map(Path::new);
+        self.entries
+            .iter()
+            .filter(|entry| {
+                if entry.match_file_name {
+                    file_name.is_some_and(|name| entry.matcher.is_match(name))
+                } else {
+                    entry.matcher.is_match(path)
+                }
+            })
+            .map(|entry| entry.password.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Debug for PasswordMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.entries.iter().map(|entry| &entry.pattern))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordMap;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn patterns_without_separator_match_file_names() -> Result<(), Box<dyn std::error::Error>> {
+        let mut map = PasswordMap::new();
+        map.insert("acme-*.pdf", "acme")?;
+        map.insert("**/suppliers/globex/*.pdf", "globex")?;
+
+        assert_eq!(
+            map.passwords_for(Path::new("/docs/in/acme-pump.pdf")),
+            vec!["acme"]
+        );
+        assert_eq!(
+            map.passwords_for(Path::new("/docs/suppliers/globex/valve.pdf")),
+            vec!["globex"]
+        );
+        assert!(map
+            .passwords_for(Path::new("/docs/suppliers/globex/old/valve.pdf"))
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn keyring_file_is_loaded_in_order() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let keyring = dir.path().join("keyring.json");
+        std::fs::write(
+            &keyring,
+            r#"[
+                {"pattern": "*.pdf", "password": "fallback"},
+                {"pattern": "secret-*.pdf", "password": "secret"}
+            ]"#,
+        )?;
+
+        let map = PasswordMap::from_keyring_file(&keyring)?;
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.passwords_for(Path::new("secret-1.pdf")),
+            vec!["fallback", "secret"]
+        );
+        assert!(!format!("{map:?}").contains("fallback"));
+        Ok(())
+    }
+}
@@ -0,0 +1,553 @@
+use crate::{QueryFilters, SearchError, SearchQuery};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Terms shorter than this only influence ranking; they are never required.
+const MIN_REQUIRED_TERM_CHARS: usize = 3;
+
+const TEXT_FIELDS: [&str; 3] = ["text_raw", "text_normalized", "section_path"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterField {
+    Standard,
+    Version,
+    Section,
+    Clause,
+}
+
+impl FilterField {
+    fn from_prefix(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "standard" => Some(FilterField::Standard),
+            "version" => Some(FilterField::Version),
+            "section" => Some(FilterField::Section),
+            "clause" => Some(FilterField::Clause),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FilterField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FilterField::Standard => "standard",
+            FilterField::Version => "version",
+            FilterField::Section => "section",
+            FilterField::Clause => "clause",
+        })
+    }
+}
+
+/// Parsed form of the query language, e.g.
+/// `"relief valve" AND (pressure OR flow) -pneumatic standard:"ISO 4413" clause:5.2.*`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "node", content = "value", rename_all = "snake_case")]
+pub enum QueryExpr {
+    Term(String),
+    Phrase(String),
+    /// Field filters only appear at the top level; they end up in `QueryFilters`.
+    Field {
+        field: FilterField,
+        value: String,
+    },
+    Not(Box<QueryExpr>),
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+}
+
+impl QueryExpr {
+    /// A word too short to be required; it only influences ranking.
+    fn is_short_term(&self) -> bool {
+        matches!(self, QueryExpr::Term(term) if term.chars().count() < MIN_REQUIRED_TERM_CHARS)
+    }
+
+    /// Excluding a short word would drop any chunk containing those letters,
+    /// so such exclusions are ignored.
+    fn is_ignored_exclusion(&self) -> bool {
+        matches!(self, QueryExpr::Not(inner) if inner.is_short_term())
+    }
+
+    /// Whether `text` satisfies the terms, phrases and operators; field
+    /// filters are left to the stores.
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            QueryExpr::Term(term) => {
+                self.is_short_term() || text.to_lowercase().contains(&term.to_lowercase())
+            }
+            QueryExpr::Phrase(phrase) => normalize(text).contains(&normalize(phrase)),
+            QueryExpr::Field { .. } => true,
+            QueryExpr::Not(_) if self.is_ignored_exclusion() => true,
+            QueryExpr::Not(inner) => !inner.matches(text),
+            QueryExpr::And(items) => items.iter().all(|item| item.matches(text)),
+            QueryExpr::Or(items) => items.iter().any(|item| item.matches(text)),
+        }
+    }
+
+    /// OpenSearch query DSL for the text part of the expression; field
+    /// filters are applied through the `filter` clause instead.
+    pub fn to_opensearch(&self) -> Value {
+        match self {
+            QueryExpr::Term(term) => json!({
+                "multi_match": {"query": term, "fields": TEXT_FIELDS}
+            }),
+            QueryExpr::Phrase(phrase) => json!({
+                "multi_match": {"query": phrase, "type": "phrase", "fields": TEXT_FIELDS}
+            }),
+            QueryExpr::Field { .. } => json!({"match_all": {}}),
+            QueryExpr::Not(_) if self.is_ignored_exclusion() => json!({"match_all": {}}),
+            QueryExpr::Not(inner) => json!({"bool": {"must_not": [inner.to_opensearch()]}}),
+            QueryExpr::And(items) => {
+                // Short words ("of", "to") only add to the score, as in `matches`.
+                let (should, must): (Vec<_>, Vec<_>) = items
+                    .iter()
+                    .filter(|item| {
+                        !matches!(item, QueryExpr::Field { .. }) && !item.is_ignored_exclusion()
+                    })
+                    .partition(|item| item.is_short_term());
+                let should = should
+                    .into_iter()
+                    .map(QueryExpr::to_opensearch)
+                    .collect::<Vec<_>>();
+                let must = must
+                    .into_iter()
+                    .map(QueryExpr::to_opensearch)
+                    .collect::<Vec<_>>();
+                match (must.is_empty(), should.is_empty()) {
+                    (true, true) => json!({"match_all": {}}),
+                    (false, true) => json!({"bool": {"must": must}}),
+                    _ => json!({"bool": {"must": must, "should": should}}),
+                }
+            }
+            QueryExpr::Or(items) => json!({
+                "bool": {
+                    "should": items.iter().map(QueryExpr::to_opensearch).collect::<Vec<_>>(),
+                    "minimum_should_match": 1
+                }
+            }),
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Parses the query language into a query whose text, required and
+    /// excluded terms, phrases and filters come from the expression.
+    pub fn parse(input: &str) -> Result<Self, SearchError> {
+        let expression = Parser::new(tokenize(input)?).parse()?;
+        let mut query = SearchQuery::default();
+        let mut text = Vec::new();
+
+        let top_level = match &expression {
+            QueryExpr::And(items) => items.as_slice(),
+            single => std::slice::from_ref(single),
+        };
+        for item in top_level {
+            match item {
+                QueryExpr::Term(term) => {
+                    if term.chars().count() >= MIN_REQUIRED_TERM_CHARS {
+                        query.mandatory_terms.push(term.to_lowercase());
+                    }
+                }
+                QueryExpr::Phrase(phrase) => query.phrases.push(phrase.clone()),
+                QueryExpr::Not(_) if item.is_ignored_exclusion() => {}
+                QueryExpr::Not(inner) => match inner.as_ref() {
+                    QueryExpr::Term(excluded) | QueryExpr::Phrase(excluded) => {
+                        query.must_not_terms.push(excluded.to_lowercase())
+                    }
+                    _ => {}
+                },
+                QueryExpr::Field { field, value } => set_filter(&mut query.filters, *field, value)?,
+                QueryExpr::And(_) | QueryExpr::Or(_) => {}
+            }
+        }
+        collect_text(&expression, &mut text);
+
+        query.text = text.join(" ");
+        query.expression = Some(expression);
+        Ok(query)
+    }
+}
+
+fn set_filter(
+    filters: &mut QueryFilters,
+    field: FilterField,
+    value: &str,
+) -> Result<(), SearchError> {
+    let slot = match field {
+        FilterField::Standard => &mut filters.standard,
+        FilterField::Version => &mut filters.version,
+        FilterField::Section => &mut filters.section_path,
+        FilterField::Clause => &mut filters.clause_id,
+    };
+    if slot.is_some() {
+        return Err(SearchError::InvalidQuery(format!(
+            "`{field}:` is given more than once"
+        )));
+    }
+    *slot = Some(value.to_string());
+    Ok(())
+}
+
+/// Positive terms and phrases, which drive ranking and the query embedding.
+fn collect_text(expression: &QueryExpr, text: &mut Vec<String>) {
+    match expression {
+        QueryExpr::Term(value) | QueryExpr::Phrase(value) => text.push(value.clone()),
+        QueryExpr::And(items) | QueryExpr::Or(items) => {
+            for item in items {
+                collect_text(item, text);
+            }
+        }
+        QueryExpr::Field { .. } | QueryExpr::Not(_) => {}
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Field(FilterField, String),
+    Open,
+    Close,
+    Minus,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, SearchError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&next) = chars.peek() {
+        match next {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Phrase(read_phrase(&mut chars)?));
+            }
+            '-' => {
+                chars.next();
+                if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                    tokens.push(Token::Minus);
+                } else {
+                    tokens.push(Token::Word("-".to_string()));
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                let field = word
+                    .split_once(':')
+                    .and_then(|(name, value)| Some((FilterField::from_prefix(name)?, value)));
+                match field {
+                    Some((field, "")) if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        tokens.push(Token::Field(field, read_phrase(&mut chars)?));
+                    }
+                    Some((field, "")) => {
+                        return Err(SearchError::InvalidQuery(format!(
+                            "`{field}:` needs a value"
+                        )))
+                    }
+                    Some((field, value)) => tokens.push(Token::Field(field, value.to_string())),
+                    None => tokens.push(Token::Word(word)),
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn read_phrase(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<String, SearchError> {
+    let mut phrase = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(phrase.trim().to_string());
+        }
+        phrase.push(c);
+    }
+    Err(SearchError::InvalidQuery(format!(
+        "unterminated phrase `\"{phrase}`"
+    )))
+}
+
+/// Recursive descent: `OR` binds loosest, then `AND` (explicit or implied by
+/// juxtaposition), then `NOT`/`-`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<QueryExpr, SearchError> {
+        if self.tokens.is_empty() {
+            return Err(SearchError::InvalidQuery("query is empty".to_string()));
+        }
+        let expression = self.or_expr()?;
+        if let Some(token) = self.tokens.get(self.position) {
+            return Err(SearchError::InvalidQuery(format!(
+                "unexpected {} in query",
+                describe(token)
+            )));
+        }
+
+        // Filters narrow the whole query, so they may only sit beside the
+        // top-level terms, never under NOT, OR or parentheses.
+        let top_level = match &expression {
+            QueryExpr::And(items) => items.as_slice(),
+            single => std::slice::from_ref(single),
+        };
+        let nested = top_level
+            .iter()
+            .filter(|item| !matches!(item, QueryExpr::Field { .. }))
+            .find_map(nested_field);
+        if let Some(field) = nested {
+            return Err(SearchError::InvalidQuery(format!(
+                "`{field}:` filters apply to the whole query and cannot be negated, grouped or combined with OR"
+            )));
+        }
+        Ok(expression)
+    }
+
+    fn peek_operator(&self, operator: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(Token::Word(word)) if word == operator)
+    }
+
+    fn or_expr(&mut self) -> Result<QueryExpr, SearchError> {
+        let mut items = vec![self.and_expr()?];
+        while self.peek_operator("OR") {
+            self.position += 1;
+            items.push(self.and_expr()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            QueryExpr::Or(items)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<QueryExpr, SearchError> {
+        let mut items = vec![self.unary()?];
+        loop {
+            if self.peek_operator("AND") {
+                self.position += 1;
+            } else if self.peek_operator("OR")
+                || matches!(self.tokens.get(self.position), None | Some(Token::Close))
+            {
+                break;
+            }
+            items.push(self.unary()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            QueryExpr::And(items)
+        })
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, SearchError> {
+        let negated = match self.tokens.get(self.position) {
+            Some(Token::Minus) => true,
+            Some(Token::Word(word)) if word == "NOT" => true,
+            _ => false,
+        };
+        if !negated {
+            return self.primary();
+        }
+        self.position += 1;
+        Ok(QueryExpr::Not(Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> Result<QueryExpr, SearchError> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| {
+            SearchError::InvalidQuery("query ends where a term was expected".to_string())
+        })?;
+        self.position += 1;
+        match token {
+            Token::Word(word) if matches!(word.as_str(), "AND" | "OR") => Err(
+                SearchError::InvalidQuery(format!("`{word}` needs a term on both sides")),
+            ),
+            Token::Word(word) => Ok(QueryExpr::Term(word)),
+            Token::Phrase(phrase) if phrase.is_empty() => {
+                Err(SearchError::InvalidQuery("empty phrase".to_string()))
+            }
+            Token::Phrase(phrase) => Ok(QueryExpr::Phrase(phrase)),
+            Token::Field(field, value) => {
+                if value.contains('*') && (field != FilterField::Clause || !value.ends_with(".*")) {
+                    return Err(SearchError::InvalidQuery(format!(
+                        "only clause filters take a wildcard, as a trailing `.*` (got `{field}:{value}`)"
+                    )));
+                }
+                Ok(QueryExpr::Field { field, value })
+            }
+            Token::Open => {
+                let inner = self.or_expr()?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Close) => {
+                        self.position += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(SearchError::InvalidQuery("missing `)`".to_string())),
+                }
+            }
+            Token::Close => Err(SearchError::InvalidQuery("unexpected `)`".to_string())),
+            Token::Minus => unreachable!("negation is handled by unary"),
+        }
+    }
+}
+
+fn nested_field(expression: &QueryExpr) -> Option<FilterField> {
+    match expression {
+        QueryExpr::Field { field, .. } => Some(*field),
+        QueryExpr::Not(inner) => nested_field(inner),
+        QueryExpr::And(items) | QueryExpr::Or(items) => items.iter().find_map(nested_field),
+        QueryExpr::Term(_) | QueryExpr::Phrase(_) => None,
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("`{word}`"),
+        Token::Phrase(phrase) => format!("`\"{phrase}\"`"),
+        Token::Field(field, value) => format!("`{field}:{value}`"),
+        Token::Open => "`(`".to_string(),
+        Token::Close => "`)`".to_string(),
+        Token::Minus => "`-`".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_phrases_boolean_groups_exclusions_and_filters() {
+        let query = SearchQuery::parse(
+            r#""relief valve" AND (pressure OR flow) -pneumatic standard:"ISO 4413" clause:5.2.*"#,
+        )
+        .expect("valid query");
+
+        assert_eq!(
+            query.expression,
+            Some(QueryExpr::And(vec![
+                QueryExpr::Phrase("relief valve".to_string()),
+                QueryExpr::Or(vec![
+                    QueryExpr::Term("pressure".to_string()),
+                    QueryExpr::Term("flow".to_string()),
+                ]),
+                QueryExpr::Not(Box::new(QueryExpr::Term("pneumatic".to_string()))),
+                QueryExpr::Field {
+                    field: FilterField::Standard,
+                    value: "ISO 4413".to_string(),
+                },
+                QueryExpr::Field {
+                    field: FilterField::Clause,
+                    value: "5.2.*".to_string(),
+                },
+            ]))
+        );
+        assert_eq!(query.text, "relief valve pressure flow");
+        assert!(query.mandatory_terms.is_empty());
+        assert_eq!(query.phrases, vec!["relief valve"]);
+        assert_eq!(query.must_not_terms, vec!["pneumatic"]);
+        assert_eq!(query.filters.standard.as_deref(), Some("ISO 4413"));
+        assert_eq!(query.filters.clause_id.as_deref(), Some("5.2.*"));
+
+        let expression = query.expression.expect("parsed");
+        assert!(expression.matches("Set the Relief  Valve by flow rate"));
+        assert!(!expression.matches("relief valve pressure for pneumatic lines"));
+        assert!(!expression.matches("valve relief pressure"));
+        assert_eq!(
+            expression.to_opensearch()["bool"]["must"][1]["bool"]["minimum_should_match"],
+            1
+        );
+    }
+
+    #[test]
+    fn plain_text_keeps_requiring_every_longer_word() {
+        let query = SearchQuery::parse("relief valve of pump").expect("valid query");
+        assert_eq!(query.text, "relief valve of pump");
+        assert_eq!(query.mandatory_terms, vec!["relief", "valve", "pump"]);
+        assert_eq!(query.all_terms_required(), vec!["relief", "valve", "pump"]);
+
+        let dsl = query.expression.expect("parsed").to_opensearch();
+        let required = |clause: &str| {
+            dsl["bool"][clause]
+                .as_array()
+                .expect("clause list")
+                .iter()
+                .map(|item| item["multi_match"]["query"].as_str().unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(required("must"), vec!["relief", "valve", "pump"]);
+        assert_eq!(required("should"), vec!["of"]);
+    }
+
+    #[test]
+    fn excluding_short_words_is_ignored() {
+        for input in ["relief valve -ab", "relief valve NOT ab"] {
+            let query = SearchQuery::parse(input).expect("valid query");
+            assert!(query.must_not_terms.is_empty(), "{input}");
+
+            let expression = query.expression.expect("parsed");
+            assert!(expression.matches("the relief valve of the lab"), "{input}");
+            let dsl = expression.to_opensearch();
+            assert_eq!(dsl["bool"]["must"].as_array().map(Vec::len), Some(2));
+            assert!(!dsl.to_string().contains("must_not"), "{input}");
+        }
+
+        let query = SearchQuery::parse("relief valve -pneumatic").expect("valid query");
+        assert_eq!(query.must_not_terms, vec!["pneumatic"]);
+    }
+
+    #[test]
+    fn malformed_queries_are_rejected() {
+        for input in [
+            "",
+            "(relief valve",
+            "\"relief valve",
+            "relief OR",
+            "pump OR standard:ISO",
+            "-clause:5.2",
+            "(pump standard:ISO) OR valve",
+            "standard:ISO*",
+            "standard:",
+            "clause:5.1 clause:5.2",
+        ] {
+            assert!(
+                matches!(SearchQuery::parse(input), Err(SearchError::InvalidQuery(_))),
+                "{input} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::{SearchCandidate, SearchError, SearchQuery};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+pub const DEFAULT_RERANK_TOP_N: usize = 50;
+
+/// Rescores the best fused hits before they are cut down to `top_k`.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Returns one relevance score per hit, in the order the hits were given.
+    async fn rerank(
+        &self,
+        query: &SearchQuery,
+        hits: &[SearchCandidate],
+    ) -> Result<Vec<f64>, SearchError>;
+}
+
+const COVERAGE_WEIGHT: f64 = 0.4;
+const PROXIMITY_WEIGHT: f64 = 0.3;
+const PHRASE_WEIGHT: f64 = 0.2;
+const CLAUSE_WEIGHT: f64 = 0.1;
+
+/// Scores hits by how many query terms they contain, how close together
+/// those terms are, whether the query appears as an exact phrase and
+/// whether the hit is the clause the query names.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicalReranker;
+
+#[async_trait]
+impl Reranker for LexicalReranker {
+    fn name(&self) -> &str {
+        "lexical"
+    }
+
+    async fn rerank(
+        &self,
+        query: &SearchQuery,
+        hits: &[SearchCandidate],
+    ) -> Result<Vec<f64>, SearchError> {
+        let query_tokens = tokens(&query.text);
+        let mut clause_ids = query_tokens
+            .iter()
+            .filter(|token| is_clause_id(token))
+            .cloned()
+            .collect::<Vec<_>>();
+        clause_ids.extend(
+            query
+                .filters
+                .clause_id
+                .iter()
+                .map(|id| id.trim_end_matches(".*").to_lowercase()),
+        );
+        let phrases = std::iter::once(query_tokens.clone())
+            .chain(query.phrases.iter().map(|phrase| tokens(phrase)))
+            .collect::<Vec<_>>();
+
+        Ok(hits
+            .iter()
+            .map(|hit| lexical_score(&query_tokens, &phrases, &clause_ids, hit))
+            .collect())
+    }
+}
+
+fn lexical_score(
+    query_tokens: &[String],
+    phrases: &[Vec<String>],
+    clause_ids: &[String],
+    hit: &SearchCandidate,
+) -> f64 {
+    let text = hit
+        .text
+        .as_deref()
+        .or_else(|| hit.chunk.as_ref().map(|chunk| chunk.text_raw.as_str()))
+        .unwrap_or_default();
+    let hit_tokens = tokens(text);
+
+    let mut terms = query_tokens.to_vec();
+    terms.sort_unstable();
+    terms.dedup();
+    let positions = terms
+        .iter()
+        .filter_map(|term| {
+            let found = hit_tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| *token == term)
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>();
+            (!found.is_empty()).then_some(found)
+        })
+        .collect::<Vec<_>>();
+
+    let coverage = if terms.is_empty() {
+        0.0
+    } else {
+        positions.len() as f64 / terms.len() as f64
+    };
+    let proximity = match smallest_window(&positions) {
+        Some(window) => positions.len() as f64 / window as f64,
+        None => 0.0,
+    };
+    let phrase = phrases.iter().any(|phrase| {
+        !phrase.is_empty()
+            && hit_tokens
+                .windows(phrase.len())
+                .any(|window| window == phrase.as_slice())
+    });
+    // Stores return hits without the full chunk, so the clause comes from the hit.
+    let clause = hit
+        .clause_id
+        .as_deref()
+        .map(str::to_lowercase)
+        .is_some_and(|hit_clause| {
+            clause_ids.iter().any(|wanted| {
+                hit_clause == *wanted || hit_clause.starts_with(&format!("{wanted}."))
+            })
+        });
+
+    COVERAGE_WEIGHT * coverage
+        + PROXIMITY_WEIGHT * proximity
+        + PHRASE_WEIGHT * f64::from(u8::from(phrase))
+        + CLAUSE_WEIGHT * f64::from(u8::from(clause))
+}
+
+/// Length in tokens of the shortest stretch of text holding one occurrence
+/// of every matched term; `positions` holds each term's occurrences.
+fn smallest_window(positions: &[Vec<usize>]) -> Option<usize> {
+    let mut occurrences = positions
+        .iter()
+        .enumerate()
+        .flat_map(|(term, found)| found.iter().map(move |&position| (position, term)))
+        .collect::<Vec<_>>();
+    occurrences.sort_unstable();
+
+    let mut counts = vec![0usize; positions.len()];
+    let mut covered = 0;
+    let mut start = 0;
+    let mut best: Option<usize> = None;
+    for end in 0..occurrences.len() {
+        let term = occurrences[end].1;
+        counts[term] += 1;
+        if counts[term] == 1 {
+            covered += 1;
+        }
+        while covered == positions.len() {
+            let window = occurrences[end].0 - occurrences[start].0 + 1;
+            best = Some(best.map_or(window, |best| best.min(window)));
+            let first = occurrences[start].1;
+            counts[first] -= 1;
+            if counts[first] == 0 {
+                covered -= 1;
+            }
+            start += 1;
+        }
+    }
+    best
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '.')
+        .map(|token| token.trim_matches('.').to_lowercase())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+fn is_clause_id(token: &str) -> bool {
+    token.contains('.')
+        && token.starts_with(|c: char| c.is_ascii_digit())
+        && token.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Calls a rerank server that speaks the text-embeddings-inference
+/// `/rerank` API, e.g. a local cross-encoder.
+pub struct HttpReranker {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankedText {
+    index: usize,
+    score: f64,
+}
+
+impl HttpReranker {
+    pub fn new(endpoint: impl Into<String>, timeout: Duration) -> Result<Self, SearchError> {
+        Ok(Self {
+            client: Client::builder().timeout(timeout).build()?,
+            endpoint: endpoint.into(),
+        })
+    }
+}
+
+#[async_trait]
+impl Reranker for HttpReranker {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    async fn rerank(
+        &self,
+        query: &SearchQuery,
+        hits: &[SearchCandidate],
+    ) -> Result<Vec<f64>, SearchError> {
+        if hits.is_empty() {
+            return Ok(Vec::new());
+        }
+        let texts = hits
+            .iter()
+            .map(|hit| {
+                hit.text
+                    .as_deref()
+                    .or_else(|| hit.chunk.as_ref().map(|chunk| chunk.text_raw.as_str()))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({
+                "query": query.text,
+                "texts": texts,
+                "truncate": true,
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SearchError::BackendResponse {
+                backend: "reranker".to_string(),
+                details: response.status().to_string(),
+            });
+        }
+
+        let scores = response
+            .json::<Vec<RerankedText>>()
+            .await?
+            .into_iter()
+            .map(|ranked| (ranked.index, ranked.score))
+            .collect::<HashMap<_, _>>();
+        (0..hits.len())
+            .map(|index| {
+                scores
+                    .get(&index)
+                    .copied()
+                    .ok_or_else(|| SearchError::BackendResponse {
+                        backend: "reranker".to_string(),
+                        details: format!("no score for text {index}"),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Reranking chosen per query; `top_n` fused hits are rescored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reranker", rename_all = "snake_case")]
+pub enum Rerank {
+    Lexical {
+        top_n: usize,
+    },
+    Http {
+        endpoint: String,
+        top_n: usize,
+        timeout_ms: u64,
+    },
+}
+
+impl Rerank {
+    /// Builds a reranker from its CLI name (`lexical` or `http`); `http`
+    /// needs an endpoint.
+    pub fn from_name(
+        name: &str,
+        endpoint: Option<&str>,
+        top_n: usize,
+        timeout: Duration,
+    ) -> Result<Self, SearchError> {
+        if top_n == 0 {
+            return Err(SearchError::InvalidQuery(
+                "rerank top_n must be at least 1".to_string(),
+            ));
+        }
+        match name.trim().to_ascii_lowercase().as_str() {
+            "lexical" => Ok(Rerank::Lexical { top_n }),
+            "http" => match endpoint {
+                Some(endpoint) => Ok(Rerank::Http {
+                    endpoint: endpoint.to_string(),
+                    top_n,
+                    timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+                }),
+                None => Err(SearchError::InvalidQuery(
+                    "the http reranker needs an endpoint".to_string(),
+                )),
+            },
+            other => Err(SearchError::InvalidQuery(format!(
+                "unknown reranker `{other}` (expected lexical or http)"
+            ))),
+        }
+    }
+
+    pub fn top_n(&self) -> usize {
+        match *self {
+            Rerank::Lexical { top_n } | Rerank::Http { top_n, .. } => top_n,
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for Rerank {
+    fn name(&self) -> &str {
+        match self {
+            Rerank::Lexical { .. } => "lexical",
+            Rerank::Http { .. } => "http",
+        }
+    }
+
+    async fn rerank(
+        &self,
+        query: &SearchQuery,
+        hits: &[SearchCandidate],
+    ) -> Result<Vec<f64>, SearchError> {
+        match self {
+            Rerank::Lexical { .. } => LexicalReranker.rerank(query, hits).await,
+            Rerank::Http {
+                endpoint,
+                timeout_ms,
+                ..
+            } => {
+                HttpReranker::new(endpoint.as_str(), Duration::from_millis(*timeout_ms))?
+                    .rerank(query, hits)
+                    .await
+            }
+        }
+    }
+}
+
+impl fmt::Display for Rerank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rerank::Lexical { top_n } => write!(f, "lexical(top_n={top_n})"),
+            Rerank::Http {
+                endpoint, top_n, ..
+            } => write!(f, "http({endpoint}, top_n={top_n})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchMode;
+    use mockito::Server;
+
+    fn hit(chunk_id: &str, text: &str, clause_id: Option<&str>) -> SearchCandidate {
+        SearchCandidate {
+            chunk_id: chunk_id.to_string(),
+            document_id: "doc".to_string(),
+            source_path: "/tmp/pump.pdf".to_string(),
+            score: 0.0,
+            source: "opensearch".to_string(),
+            chunk: None,
+            text: Some(text.to_string()),
+            mode: SearchMode::Keyword,
+            ocr_confidence: None,
+            citation: None,
+            clause_id: clause_id.map(str::to_string),
+            collapsed: 0,
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            highlights: Vec::new(),
+            explanation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn lexical_scores_favour_phrases_proximity_and_named_clauses() {
+        let query = SearchQuery {
+            text: "relief valve 5.2".to_string(),
+            ..SearchQuery::default()
+        };
+        let hits = [
+            hit(
+                "scattered",
+                "the valve is fitted before the relief line",
+                None,
+            ),
+            hit("phrase", "set the relief valve 5.2 bar above", None),
+            hit("clause", "relief valve settings", Some("5.2.1")),
+            hit("unrelated", "pump bearings", None),
+        ];
+
+        let scores = LexicalReranker
+            .rerank(&query, &hits)
+            .await
+            .expect("lexical reranking");
+
+        assert!(scores[1] > scores[2]);
+        assert!(scores[2] > scores[0]);
+        assert_eq!(scores[3], 0.0);
+        assert!((scores[1] - 0.9).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn http_reranker_maps_scores_back_to_hit_order() {
+        let mut server = Server::new_async().await;
+        let rerank = server
+            .mock("POST", "/rerank")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "query": "relief valve",
+                "texts": ["pump bearings", "relief valve setting"],
+            })))
+            .with_status(200)
+            .with_body(r#"[{"index": 1, "score": 0.98}, {"index": 0, "score": 0.02}]"#)
+            .create_async()
+            .await;
+        let reranker =
+            HttpReranker::new(format!("{}/rerank", server.url()), Duration::from_secs(5))
+                .expect("client");
+
+        let scores = reranker
+            .rerank(
+                &SearchQuery {
+                    text: "relief valve".to_string(),
+                    ..SearchQuery::default()
+                },
+                &[
+                    hit("a", "pump bearings", None),
+                    hit("b", "relief valve setting", None),
+                ],
+            )
+            .await
+            .expect("rerank response");
+
+        rerank.assert_async().await;
+        assert_eq!(scores, vec![0.02, 0.98]);
+    }
+}
@@ -0,0 +1,518 @@
+use crate::error::IngestError;
+use crate::extractor::{extract_page_texts, ExtractionMethod, PageText};
+use crate::models::IngestionOptions;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const SNIFF_BYTES: u64 = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    #[default]
+    Pdf,
+    Html,
+    Markdown,
+    Text,
+}
+
+impl DocumentFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentFormat::Pdf => "pdf",
+            DocumentFormat::Html => "html",
+            DocumentFormat::Markdown => "markdown",
+            DocumentFormat::Text => "text",
+        }
+    }
+}
+
+impl fmt::Display for DocumentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DocumentFormat {
+    type Err = IngestError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pdf" => Ok(DocumentFormat::Pdf),
+            "html" | "htm" => Ok(DocumentFormat::Html),
+            "markdown" | "md" => Ok(DocumentFormat::Markdown),
+            "text" | "txt" => Ok(DocumentFormat::Text),
+            other => Err(IngestError::InvalidArgument(format!(
+                "unknown document format `{other}` (expected pdf, html, markdown or text)"
+            ))),
+        }
+    }
+}
+
+/// A document format the ingestion pipeline can turn into pages. Formats
+/// without physical pages emit one page per heading section, with the heading
+/// path in `PageText::section`.
+pub trait DocumentSource {
+    fn format(&self) -> DocumentFormat;
+
+    /// Lower-case file extensions, without the dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Recognises the format from the first bytes of a file without an
+    /// extension.
+    fn sniff(&self, _head: &[u8]) -> bool {
+        false
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        options: &IngestionOptions,
+    ) -> Result<Vec<PageText>, IngestError>;
+}
+
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: Vec<Arc<dyn DocumentSource + Send + Sync>>,
+}
+
+impl fmt::Debug for SourceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.sources.iter().map(|source| source.format()))
+            .finish()
+    }
+}
+
+/// PDFs only; other formats are opt-in through `for_formats` or `all`.
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        Self::new().with(PdfSource)
+    }
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Every built-in format.
+    pub fn all() -> Self {
+        Self::new()
+            .with(PdfSource)
+            .with(HtmlSource)
+            .with(MarkdownSource)
+            .with(TextSource)
+    }
+
+    pub fn for_formats(formats: &[DocumentFormat]) -> Self {
+        let mut registry = Self::all();
+        registry
+            .sources
+            .retain(|source| formats.contains(&source.format()));
+        registry
+    }
+
+    pub fn with<S>(mut self, source: S) -> Self
+    where
+        S: DocumentSource + Send + Sync + 'static,
+    {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    pub fn formats(&self) -> Vec<DocumentFormat> {
+        self.sources.iter().map(|source| source.format()).collect()
+    }
+
+    pub fn for_path(&self, path: &Path) -> Option<&(dyn DocumentSource + Send + Sync)> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        // Files with an unknown extension are not opened; only extensionless
+        // ones are sniffed.
+        if let Some(extension) = &extension {
+            return self
+                .sources
+                .iter()
+                .find(|source| source.extensions().contains(&extension.as_str()))
+                .map(|source| source.as_ref());
+        }
+
+        let head = read_head(path).ok()?;
+        self.sources
+            .iter()
+            .find(|source| source.sniff(&head))
+            .map(|source| source.as_ref())
+    }
+}
+
+fn read_head(path: &Path) -> Result<Vec<u8>, IngestError> {
+    let mut head = Vec::new();
+    fs::File::open(path)?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfSource;
+
+impl DocumentSource for PdfSource {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Pdf
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        head.starts_with(b"%PDF-")
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        options: &IngestionOptions,
+    ) -> Result<Vec<PageText>, IngestError> {
+        extract_page_texts(path, options)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlSource;
+
+impl DocumentSource for HtmlSource {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Html
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm", "xhtml"]
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let head = String::from_utf8_lossy(head);
+        let head = head.trim_start_matches('\u{feff}').trim_start();
+        ["<!doctype html", "<html"].iter().any(|prefix| {
+            head.get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        })
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        _options: &IngestionOptions,
+    ) -> Result<Vec<PageText>, IngestError> {
+        Ok(html_sections(&read_text(path)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownSource;
+
+impl DocumentSource for MarkdownSource {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Markdown
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["md", "markdown"]
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        _options: &IngestionOptions,
+    ) -> Result<Vec<PageText>, IngestError> {
+        Ok(markdown_sections(&read_text(path)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextSource;
+
+impl DocumentSource for TextSource {
+    fn format(&self) -> DocumentFormat {
+        DocumentFormat::Text
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt", "text"]
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        _options: &IngestionOptions,
+    ) -> Result<Vec<PageText>, IngestError> {
+        Ok(read_text(path)?
+            .split('\u{000c}')
+            .enumerate()
+            .filter(|(_, page)| !page.trim().is_empty())
+            .map(|(index, page)| PageText {
+                number: index as u32 + 1,
+                text: page.trim().to_string(),
+                method: ExtractionMethod::TextLayer,
+                confidence: None,
+                section: None,
+                spans: Vec::new(),
+                failure: None,
+            })
+            .collect())
+    }
+}
+
+fn read_text(path: &Path) -> Result<String, IngestError> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+#[derive(Default)]
+struct SectionBuilder {
+    headings: Vec<(u8, String)>,
+    section: Option<String>,
+    text: String,
+    pages: Vec<PageText>,
+}
+
+impl SectionBuilder {
+    fn heading(&mut self, level: u8, title: &str) {
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() {
+            return;
+        }
+
+        self.page_break();
+        self.headings.retain(|(open, _)| *open < level);
+        self.headings.push((level, title.clone()));
+        self.section = Some(
+            self.headings
+                .iter()
+                .map(|(_, title)| title.as_str())
+                .collect::<Vec<_>>()
+                .join(" > "),
+        );
+        self.text.push_str(&title);
+        self.text.push_str("\n\n");
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn block_break(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push_str("\n\n");
+        }
+    }
+
+    fn page_break(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+
+        self.pages.push(PageText {
+            number: self.pages.len() as u32 + 1,
+            text: text.to_string(),
+            method: ExtractionMethod::TextLayer,
+            confidence: None,
+            section: self.section.clone(),
+            spans: Vec::new(),
+            failure: None,
+        });
+    }
+
+    fn finish(mut self) -> Vec<PageText> {
+        self.page_break();
+        self.pages
+    }
+}
+
+fn markdown_sections(markdown: &str) -> Vec<PageText> {
+    let mut sections = SectionBuilder::default();
+    let mut heading = None::<(u8, String)>;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((level as u8, String::new()))
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, title)) = heading.take() {
+                    sections.heading(level, &title);
+                }
+            }
+            Event::Text(text) | Event::Code(text) => match &mut heading {
+                Some((_, title)) => title.push_str(&text),
+                None => sections.push_text(&text),
+            },
+            Event::SoftBreak | Event::HardBreak => match &mut heading {
+                Some((_, title)) => title.push(' '),
+                None => sections.push_text("\n"),
+            },
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::TableRow
+                | TagEnd::TableHead,
+            ) => sections.block_break(),
+            Event::End(TagEnd::TableCell) => sections.push_text(" "),
+            _ => {}
+        }
+    }
+
+    sections.finish()
+}
+
+fn html_sections(html: &str) -> Vec<PageText> {
+    let document = Html::parse_document(html);
+    let mut sections = SectionBuilder::default();
+    walk_html(document.root_element(), &mut sections);
+    sections.finish()
+}
+
+fn walk_html(element: ElementRef<'_>, sections: &mut SectionBuilder) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if text.starts_with(char::is_whitespace) && !collapsed.is_empty() {
+                    sections.push_text(" ");
+                }
+                sections.push_text(&collapsed);
+                if text.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+                    sections.push_text(" ");
+                }
+            }
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let name = child.value().name();
+                match name {
+                    "head" | "script" | "style" | "noscript" | "template" | "nav" => {}
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        let level = name[1..].parse().unwrap_or(1);
+                        sections.heading(level, &child.text().collect::<String>());
+                    }
+                    "br" => sections.push_text("\n"),
+                    "td" | "th" => {
+                        walk_html(child, sections);
+                        sections.push_text(" ");
+                    }
+                    "p" | "div" | "li" | "tr" | "pre" | "blockquote" | "section" | "article"
+                    | "table" | "ul" | "ol" | "dl" | "dt" | "dd" | "figure" | "header"
+                    | "footer" | "main" => {
+                        sections.block_break();
+                        walk_html(child, sections);
+                        sections.block_break();
+                    }
+                    _ => walk_html(child, sections),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{html_sections, markdown_sections, DocumentFormat, SourceRegistry};
+    use crate::IngestionOptions;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn markdown_headings_become_sections() {
+        let pages = markdown_sections(
+            "# Pump manual\n\nIntro text.\n\n## 5.2 Relief valve\n\nSet to **210 bar**.\n\n- check seals\n- log result\n\n# Appendix\n\nTorque table.",
+        );
+
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].section.as_deref(), Some("Pump manual"));
+        assert_eq!(
+            pages[1].section.as_deref(),
+            Some("Pump manual > 5.2 Relief valve")
+        );
+        assert!(pages[1].text.starts_with("5.2 Relief valve"));
+        assert!(pages[1].text.contains("Set to 210 bar."));
+        assert!(pages[1].text.contains("check seals\n\nlog result"));
+        assert_eq!(pages[2].section.as_deref(), Some("Appendix"));
+        assert_eq!(pages[2].number, 3);
+    }
+
+    #[test]
+    fn html_skips_scripts_and_splits_on_headings() {
+        let pages = html_sections(
+            "<!DOCTYPE html><html><head><title>x</title><style>p{}</style></head><body>\
+             <nav>Home | Search</nav>\
+             <h1>Maintenance</h1><p>Weekly &amp; monthly checks.</p><script>track()</script>\
+             <h2>Filters</h2><table><tr><td>Filter</td><td>500 h</td></tr></table>\
+             </body></html>",
+        );
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].text, "Maintenance\n\nWeekly & monthly checks.");
+        assert_eq!(pages[1].section.as_deref(), Some("Maintenance > Filters"));
+        assert!(pages[1].text.contains("Filter 500 h"));
+        assert!(!pages.iter().any(|page| page.text.contains("track")));
+    }
+
+    #[test]
+    fn registry_selects_by_extension_then_magic_bytes() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let registry = SourceRegistry::all();
+
+        let notes = dir.path().join("NOTES.MD");
+        fs::write(&notes, "# Notes")?;
+        let export = dir.path().join("export");
+        fs::write(&export, "<!doctype html><p>Exported page</p>")?;
+        let scan = dir.path().join("scan");
+        fs::write(&scan, b"%PDF-1.7\n")?;
+        let renamed = dir.path().join("scan.bin");
+        fs::write(&renamed, b"%PDF-1.7\n")?;
+        let image = dir.path().join("photo.png");
+        fs::write(&image, b"\x89PNG\r\n")?;
+
+        let format = |path| registry.for_path(path).map(|source| source.format());
+        assert_eq!(format(&notes), Some(DocumentFormat::Markdown));
+        assert_eq!(format(&export), Some(DocumentFormat::Html));
+        assert_eq!(format(&scan), Some(DocumentFormat::Pdf));
+        assert_eq!(format(&image), None);
+        assert_eq!(format(&renamed), None);
+
+        let pdf_only = SourceRegistry::default();
+        assert_eq!(pdf_only.formats(), vec![DocumentFormat::Pdf]);
+        assert!(pdf_only.for_path(&notes).is_none());
+        assert_eq!(
+            SourceRegistry::for_formats(&[DocumentFormat::Pdf]).formats(),
+            pdf_only.formats()
+        );
+
+        let pages = registry
+            .for_path(&export)
+            .expect("html source")
+            .extract(&export, &IngestionOptions::default())?;
+        assert_eq!(pages[0].text, "Exported page");
+        Ok(())
+    }
+}
@@ -19,6 +19,17 @@ impl StoreHit {
             source_path: String::new(),
             score: self.score,
             source: self.source,
+            ocr_confidence: self.chunk.as_ref().and_then(|chunk| chunk.ocr_confidence),
+            citation: self.chunk.as_ref().map(PdfChunk::citation),
+            clause_id: self
+                .chunk
+                .as_ref()
+                .and_then(|chunk| chunk.clause_id.clone()),
+            collapsed: 0,
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            highlights: Vec::new(),
+            explanation: None,
             chunk: self.chunk,
             text: Some(self.text),
             mode,
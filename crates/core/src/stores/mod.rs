@@ -2,6 +2,50 @@ pub mod neo4j;
 pub mod opensearch;
 pub mod qdrant;
 
+use crate::models::{citation_section, Citation, PageSpan};
+use serde_json::Value;
+
 pub use neo4j::Neo4jStore;
 pub use opensearch::OpenSearchStore;
 pub use qdrant::QdrantStore;
+
+/// Rebuilds a hit's citation from the chunk fields stored alongside it.
+pub(crate) fn citation_from_payload(payload: &Value) -> Option<Citation> {
+    let page = |key: &str| {
+        payload
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|page| u32::try_from(page).ok())
+    };
+    let page_start = page("page_start")?;
+    let title = payload
+        .get("title")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| {
+            let source_path = payload.get("source_path").and_then(Value::as_str)?;
+            std::path::Path::new(source_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    let page_spans = payload
+        .get("page_spans")
+        .cloned()
+        .and_then(|spans| serde_json::from_value::<Vec<PageSpan>>(spans).ok())
+        .unwrap_or_default();
+
+    Some(Citation {
+        title,
+        page_start,
+        page_end: page("page_end").unwrap_or(page_start),
+        section: citation_section(
+            payload.get("clause_id").and_then(Value::as_str),
+            payload
+                .get("section_path")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        ),
+        page_spans,
+    })
+}
@@ -1,5 +1,5 @@
 use crate::traits::GraphIndex;
-use crate::{PdfChunk, SearchCandidate, SearchError, SearchMode};
+use crate::{Explanation, PdfChunk, SearchCandidate, SearchError, SearchMode, SearchQuery};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
@@ -95,12 +95,13 @@ impl GraphIndex for Neo4jStore {
     async fn related_chunks(
         &self,
         chunk_ids: &[String],
+        query: &SearchQuery,
     ) -> Result<Vec<SearchCandidate>, SearchError> {
         if chunk_ids.is_empty() {
             return Ok(Vec::new());
         }
 
-        let query = r#"
+        let statement = r#"
             MATCH (c:Chunk)-[:REFERENCES]->(ref:Clause)
             WHERE c.chunk_id IN $chunk_ids
             OPTIONAL MATCH (ref)-[:CITED_BY]->(related:Clause)
@@ -111,7 +112,9 @@ impl GraphIndex for Neo4jStore {
                             coalesce(rchunk.text, '') AS text,
                             rchunk.section_path AS section,
                             rchunk.source_path AS source_path,
-                            d.document_id AS document_id
+                            d.document_id AS document_id,
+                            ref.clause_id AS via_clause,
+                            related.clause_id AS related_clause
             LIMIT 20;
         "#;
 
@@ -122,7 +125,7 @@ impl GraphIndex for Neo4jStore {
             .json(&json!({
                 "statements": [
                     {
-                        "statement": query,
+                        "statement": statement,
                         "parameters": {"chunk_ids": chunk_ids}
                     }
                 ]
@@ -140,45 +143,48 @@ impl GraphIndex for Neo4jStore {
         let body: Value = response.json().await?;
         let rows = extract_rows(&body);
 
-        let mut hits = Vec::new();
-        for row in rows {
-            if let Some(values) = row.as_array() {
-                if values.len() >= 6 {
-                    let chunk_id = values
-                        .get(1)
-                        .and_then(Value::as_str)
-                        .unwrap_or_default()
-                        .to_string();
-                    let text = values
-                        .get(2)
-                        .and_then(Value::as_str)
-                        .unwrap_or_default()
-                        .to_string();
-                    let source_path = values
-                        .get(4)
-                        .and_then(Value::as_str)
-                        .unwrap_or_default()
-                        .to_string();
-                    let document_id = values
-                        .get(5)
-                        .and_then(Value::as_str)
-                        .unwrap_or_default()
-                        .to_string();
-                    hits.push(SearchCandidate {
-                        chunk_id,
-                        document_id,
-                        source_path,
-                        score: 0.6,
-                        source: "neo4j".to_string(),
-                        chunk: None,
-                        text: Some(text),
-                        mode: SearchMode::Graph,
-                    });
-                }
-            }
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| row.as_array())
+            .filter_map(|values| related_hit(values, query.explain))
+            .collect())
+    }
+
+    async fn delete_graph_documents(&self, document_ids: &[String]) -> Result<(), SearchError> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+
+        let cypher = r#"
+            MATCH (doc:Document)
+            WHERE doc.document_id IN $document_ids
+            OPTIONAL MATCH (doc)-[:HAS_CHUNK]->(c:Chunk)
+            DETACH DELETE c, doc;
+        "#;
+
+        let response = self
+            .client
+            .post(self.tx_url())
+            .basic_auth(&self.username, Some(&self.password))
+            .json(&json!({
+                "statements": [
+                    {
+                        "statement": cypher,
+                        "parameters": {"document_ids": document_ids}
+                    }
+                ]
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SearchError::BackendResponse {
+                backend: "neo4j".to_string(),
+                details: response.status().to_string(),
+            });
         }
 
-        Ok(hits)
+        Ok(())
     }
 }
 
@@ -217,3 +223,113 @@ fn extract_rows(payload: &Value) -> Vec<&Value> {
             .unwrap_or_default(),
     }
 }
+
+/// The graph hit for a `related_chunks` row, with its path attached only when
+/// the query asked for explanations.
+fn related_hit(values: &[Value], explain: bool) -> Option<SearchCandidate> {
+    if values.len() < 6 {
+        return None;
+    }
+    let chunk_id = values
+        .get(1)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let text = values
+        .get(2)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let source_path = values
+        .get(4)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let document_id = values
+        .get(5)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    Some(SearchCandidate {
+        chunk_id,
+        document_id,
+        source_path,
+        score: 0.6,
+        source: "neo4j".to_string(),
+        chunk: None,
+        text: Some(text),
+        mode: SearchMode::Graph,
+        ocr_confidence: None,
+        citation: None,
+        clause_id: None,
+        collapsed: 0,
+        context_before: Vec::new(),
+        context_after: Vec::new(),
+        highlights: Vec::new(),
+        explanation: explain.then(|| Explanation {
+            graph_path: graph_path(values),
+            ..Explanation::default()
+        }),
+    })
+}
+
+/// `chunk <seed> -REFERENCES-> clause <ref> -CITED_BY-> clause <related> -> chunk <hit>`
+/// from a `related_chunks` row.
+fn graph_path(row: &[Value]) -> Vec<String> {
+    let column = |index: usize| row.get(index).and_then(Value::as_str);
+    let mut path = Vec::new();
+    if let Some(seed) = column(0) {
+        path.push(format!("chunk {seed}"));
+    }
+    if let Some(clause) = column(6) {
+        path.push(format!("REFERENCES clause {clause}"));
+    }
+    if let Some(clause) = column(7) {
+        path.push(format!("CITED_BY clause {clause}"));
+    }
+    if let Some(chunk) = column(1) {
+        path.push(format!("chunk {chunk}"));
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> Vec<Value> {
+        vec![
+            json!("seed"),
+            json!("hit"),
+            json!("see clause 5.2"),
+            json!("5"),
+            json!("/docs/a.pdf"),
+            json!("doc-a"),
+            json!("5.1"),
+            json!("5.2"),
+        ]
+    }
+
+    #[test]
+    fn graph_path_is_attached_only_when_explaining() {
+        let plain = related_hit(&row(), false).expect("hit");
+        assert_eq!(plain.chunk_id, "hit");
+        assert!(plain.explanation.is_none());
+
+        let explained = related_hit(&row(), true).expect("hit");
+        assert_eq!(
+            explained.explanation.expect("explanation").graph_path,
+            vec![
+                "chunk seed",
+                "REFERENCES clause 5.1",
+                "CITED_BY clause 5.2",
+                "chunk hit",
+            ]
+        );
+    }
+
+    #[test]
+    fn short_rows_are_skipped() {
+        assert!(related_hit(&row()[..5], true).is_none());
+    }
+}
@@ -1,10 +1,16 @@
+use crate::highlight::{from_tagged, MAX_SNIPPETS, POST_TAG, PRE_TAG, SNIPPET_CHARS};
 use crate::models::PdfChunk;
+use crate::stores::citation_from_payload;
 use crate::traits::VectorIndex;
-use crate::{traits::KeywordIndex, SearchCandidate, SearchError, SearchMode, SearchQuery};
+use crate::{
+    traits::KeywordIndex, ChunkContext, ContextChunk, Explanation, SearchCandidate, SearchError,
+    SearchMode, SearchQuery,
+};
 use async_trait::async_trait;
 use reqwest::Client;
 use reqwest::StatusCode;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct OpenSearchStore {
@@ -22,6 +28,29 @@ impl OpenSearchStore {
         }
     }
 
+    async fn search_hits(&self, body: &Value) -> Result<Vec<Value>, SearchError> {
+        let response = self
+            .client
+            .post(format!("{}/{}/_search", self.endpoint, self.index_name))
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SearchError::BackendResponse {
+                backend: "opensearch".to_string(),
+                details: response.status().to_string(),
+            });
+        }
+
+        let response_json: Value = response.json().await?;
+        Ok(response_json
+            .pointer("/hits/hits")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default())
+    }
+
     pub async fn ensure_index(&self) -> Result<(), SearchError> {
         let response = self
             .client
@@ -67,7 +96,11 @@ impl OpenSearchStore {
                         "version": {"type": "keyword"},
                         "page_start": {"type": "integer"},
                         "page_end": {"type": "integer"},
-                        "chunk_index": {"type": "long"}
+                        "title": {"type": "keyword"},
+                        "page_spans": {"type": "object", "enabled": false},
+                        "chunk_index": {"type": "long"},
+                        "extraction_method": {"type": "keyword"},
+                        "ocr_confidence": {"type": "float"}
                     }
                 }
             }))
@@ -104,10 +137,13 @@ impl KeywordIndex for OpenSearchStore {
                 "clause_id": chunk.clause_id,
                 "page_start": chunk.page_start,
                 "page_end": chunk.page_end,
+                "page_spans": chunk.page_spans,
+                "title": chunk.title,
                 "chunk_index": chunk.chunk_index,
                 "text_raw": chunk.text_raw,
                 "text_normalized": chunk.text_normalized,
                 "kind": format!("{:?}", chunk.kind),
+                "extraction_method": chunk.extraction_method,
                 "ocr_confidence": chunk.ocr_confidence,
                 "references": chunk.references,
                 "units": chunk.units,
@@ -148,48 +184,37 @@ impl KeywordIndex for OpenSearchStore {
         &self,
         query: &SearchQuery,
     ) -> Result<Vec<SearchCandidate>, SearchError> {
+        let text_query = match &query.expression {
+            Some(expression) => expression.to_opensearch(),
+            None => json!({
+                "multi_match": {
+                    "query": query.text,
+                    "fields": ["text_raw", "text_normalized", "section_path"]
+                }
+            }),
+        };
         let body = json!({
             "size": query.top_k,
+            "explain": query.explain,
             "query": {
                 "bool": {
-                    "must": [
-                        {
-                            "multi_match": {
-                                "query": query.text,
-                                "fields": ["text_raw", "text_normalized", "section_path"]
-                            }
-                        }
-                    ],
-                    "filter": build_filters(&query.filters)
+                    "must": [text_query],
+                    "filter": build_filters(query)
                 }
             },
             "highlight": {
+                "pre_tags": [PRE_TAG],
+                "post_tags": [POST_TAG],
                 "fields": {
-                    "text_raw": {}
+                    "text_raw": {
+                        "fragment_size": SNIPPET_CHARS,
+                        "number_of_fragments": MAX_SNIPPETS
+                    }
                 }
             }
         });
 
-        let response = self
-            .client
-            .post(format!("{}/{}/_search", self.endpoint, self.index_name))
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(SearchError::BackendResponse {
-                backend: "opensearch".to_string(),
-                details: response.status().to_string(),
-            });
-        }
-
-        let response_json: Value = response.json().await?;
-        let hits = response_json
-            .pointer("/hits/hits")
-            .and_then(Value::as_array)
-            .cloned()
-            .unwrap_or_default();
+        let hits = self.search_hits(&body).await?;
 
         let mut result = Vec::new();
 
@@ -220,6 +245,18 @@ impl KeywordIndex for OpenSearchStore {
                 .and_then(Value::as_str)
                 .unwrap_or_default()
                 .to_string();
+            let ocr_confidence = source
+                .pointer("/ocr_confidence")
+                .and_then(Value::as_f64)
+                .map(|confidence| confidence as f32);
+            let highlights = raw
+                .pointer("/highlight/text_raw")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(from_tagged)
+                .collect();
 
             result.push(SearchCandidate {
                 chunk_id,
@@ -230,11 +267,163 @@ impl KeywordIndex for OpenSearchStore {
                 chunk: None,
                 text: Some(text),
                 mode: SearchMode::Keyword,
+                ocr_confidence,
+                citation: citation_from_payload(&source),
+                clause_id: source
+                    .get("clause_id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights,
+                explanation: raw.get("_explanation").map(|explanation| Explanation {
+                    keyword_explanation: Some(explanation.clone()),
+                    ..Explanation::default()
+                }),
             });
         }
 
         Ok(result)
     }
+
+    async fn delete_keyword_documents(&self, document_ids: &[String]) -> Result<(), SearchError> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/{}/_delete_by_query?refresh=true",
+                self.endpoint, self.index_name
+            ))
+            .json(&json!({
+                "query": {"terms": {"document_id": document_ids}}
+            }))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        if !response.status().is_success() {
+            return Err(SearchError::BackendResponse {
+                backend: "opensearch".to_string(),
+                details: response.status().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn neighbouring_chunks(
+        &self,
+        chunk_ids: &[String],
+        window: usize,
+    ) -> Result<HashMap<String, ChunkContext>, SearchError> {
+        if chunk_ids.is_empty() || window == 0 {
+            return Ok(HashMap::new());
+        }
+
+        let anchors = self
+            .search_hits(&json!({
+                "size": chunk_ids.len(),
+                "_source": ["document_id", "chunk_index"],
+                "query": {"ids": {"values": chunk_ids}}
+            }))
+            .await?
+            .iter()
+            .filter_map(chunk_position)
+            .collect::<Vec<_>>();
+        if anchors.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ranges = anchors
+            .iter()
+            .map(|(_, document_id, chunk_index)| {
+                json!({
+                    "bool": {
+                        "filter": [
+                            {"term": {"document_id": document_id}},
+                            {"range": {"chunk_index": {
+                                "gte": chunk_index.saturating_sub(window as u64),
+                                "lte": chunk_index + window as u64
+                            }}}
+                        ]
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let neighbours = self
+            .search_hits(&json!({
+                "size": anchors.len() * (2 * window + 1),
+                "_source": ["document_id", "chunk_index", "text_raw"],
+                "query": {"bool": {"should": ranges, "minimum_should_match": 1}},
+                "sort": [{"chunk_index": "asc"}]
+            }))
+            .await?;
+
+        Ok(group_neighbours(&anchors, &neighbours, window))
+    }
+}
+
+/// `(chunk_id, document_id, chunk_index)` of a search hit.
+fn chunk_position(raw: &Value) -> Option<(String, String, u64)> {
+    Some((
+        raw.pointer("/_id")?.as_str()?.to_string(),
+        raw.pointer("/_source/document_id")?.as_str()?.to_string(),
+        raw.pointer("/_source/chunk_index")?.as_u64()?,
+    ))
+}
+
+/// Splits `neighbours` into the chunks before and after each anchor, in
+/// reading order.
+fn group_neighbours(
+    anchors: &[(String, String, u64)],
+    neighbours: &[Value],
+    window: usize,
+) -> HashMap<String, ChunkContext> {
+    let mut neighbours = neighbours
+        .iter()
+        .filter_map(|raw| {
+            let (chunk_id, document_id, chunk_index) = chunk_position(raw)?;
+            let text = raw
+                .pointer("/_source/text_raw")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            Some((
+                document_id,
+                ContextChunk {
+                    chunk_id,
+                    chunk_index,
+                    text,
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+    neighbours.sort_by_key(|(_, chunk)| chunk.chunk_index);
+
+    anchors
+        .iter()
+        .map(|(chunk_id, document_id, chunk_index)| {
+            let mut context = ChunkContext::default();
+            for (neighbour_document, chunk) in &neighbours {
+                if neighbour_document != document_id
+                    || chunk.chunk_index.abs_diff(*chunk_index) > window as u64
+                {
+                    continue;
+                }
+                if chunk.chunk_index < *chunk_index {
+                    context.before.push(chunk.clone());
+                } else if chunk.chunk_index > *chunk_index {
+                    context.after.push(chunk.clone());
+                }
+            }
+            (chunk_id.clone(), context)
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -254,9 +443,14 @@ impl VectorIndex for OpenSearchStore {
     ) -> Result<Vec<SearchCandidate>, SearchError> {
         Ok(Vec::new())
     }
+
+    async fn delete_vector_documents(&self, _document_ids: &[String]) -> Result<(), SearchError> {
+        Ok(())
+    }
 }
 
-fn build_filters(filters: &crate::models::QueryFilters) -> Vec<Value> {
+fn build_filters(query: &SearchQuery) -> Vec<Value> {
+    let filters = &query.filters;
     let mut predicates = Vec::new();
 
     if let Some(standard) = &filters.standard {
@@ -269,8 +463,64 @@ fn build_filters(filters: &crate::models::QueryFilters) -> Vec<Value> {
         predicates.push(json!({"term": {"section_path": section}}));
     }
     if let Some(clause) = &filters.clause_id {
-        predicates.push(json!({"term": {"clause_id": clause}}));
+        predicates.push(match clause.strip_suffix(".*") {
+            Some(parent) => json!({
+                "bool": {
+                    "should": [
+                        {"term": {"clause_id": parent}},
+                        {"prefix": {"clause_id": format!("{parent}.")}}
+                    ],
+                    "minimum_should_match": 1
+                }
+            }),
+            None => json!({"term": {"clause_id": clause}}),
+        });
+    }
+    if let Some(min_confidence) = query.min_ocr_confidence {
+        predicates.push(json!({
+            "bool": {
+                "should": [
+                    {"range": {"ocr_confidence": {"gte": min_confidence}}},
+                    {"bool": {"must_not": {"exists": {"field": "ocr_confidence"}}}}
+                ],
+                "minimum_should_match": 1
+            }
+        }));
     }
 
     predicates
 }
+
+#[cfg(test)]
+mod tests {
+    use super::group_neighbours;
+    use serde_json::json;
+
+    #[test]
+    fn neighbours_are_split_around_each_anchor_within_its_document() {
+        let hit = |id: &str, document_id: &str, chunk_index: u64| {
+            json!({
+                "_id": id,
+                "_source": {"document_id": document_id, "chunk_index": chunk_index, "text_raw": id}
+            })
+        };
+        let neighbours = vec![
+            hit("pump-5", "pump", 5),
+            hit("pump-3", "pump", 3),
+            hit("pump-4", "pump", 4),
+            hit("pump-6", "pump", 6),
+            hit("valve-3", "valve", 3),
+        ];
+        let anchors = vec![("pump-4".to_string(), "pump".to_string(), 4)];
+
+        let context = &group_neighbours(&anchors, &neighbours, 1)["pump-4"];
+        let ids = |chunks: &[crate::ContextChunk]| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.chunk_id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&context.before), vec!["pump-3"]);
+        assert_eq!(ids(&context.after), vec!["pump-5"]);
+    }
+}
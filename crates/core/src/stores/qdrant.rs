@@ -1,8 +1,10 @@
+use crate::stores::citation_from_payload;
 use crate::traits::{KeywordIndex, VectorIndex};
 use crate::{PdfChunk, SearchCandidate, SearchError, SearchMode, SearchQuery};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 
 pub struct QdrantStore {
     endpoint: String,
@@ -34,6 +36,27 @@ impl QdrantStore {
         }
         Ok(())
     }
+
+    async fn delete_points(&self, filter: Value) -> Result<(), SearchError> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/collections/{}/points/delete?wait=true",
+                self.endpoint, self.collection
+            ))
+            .json(&json!({ "filter": filter }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SearchError::BackendResponse {
+                backend: "qdrant".to_string(),
+                details: response.status().to_string(),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -48,6 +71,10 @@ impl KeywordIndex for QdrantStore {
     ) -> Result<Vec<SearchCandidate>, SearchError> {
         Ok(Vec::new())
     }
+
+    async fn delete_keyword_documents(&self, _document_ids: &[String]) -> Result<(), SearchError> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -78,15 +105,20 @@ impl VectorIndex for QdrantStore {
                 }
 
                 let payload = json!({
+                    "chunk_id": chunk.chunk_id,
                     "document_id": chunk.document_id,
                     "source_path": chunk.source_path,
                     "section_path": chunk.section_path,
                     "clause_id": chunk.clause_id,
+                    "clause_prefixes": clause_prefixes(chunk.clause_id.as_deref()),
                     "page_start": chunk.page_start,
                     "page_end": chunk.page_end,
+                    "page_spans": chunk.page_spans,
+                    "title": chunk.title,
                     "chunk_index": chunk.chunk_index,
                     "text_raw": chunk.text_raw,
                     "kind": format!("{:?}", chunk.kind),
+                    "extraction_method": chunk.extraction_method,
                     "ocr_confidence": chunk.ocr_confidence,
                     "references": chunk.references,
                     "version": chunk.version,
@@ -94,7 +126,7 @@ impl VectorIndex for QdrantStore {
                 });
 
                 Ok(json!({
-                    "id": chunk.chunk_index,
+                    "id": point_id(&chunk.chunk_id),
                     "vector": embedding,
                     "payload": payload,
                 }))
@@ -105,6 +137,15 @@ impl VectorIndex for QdrantStore {
             return Ok(());
         }
 
+        let mut document_ids = chunks
+            .iter()
+            .map(|chunk| chunk.document_id.clone())
+            .collect::<Vec<_>>();
+        document_ids.sort_unstable();
+        document_ids.dedup();
+        self.delete_points(legacy_points_filter(&document_ids))
+            .await?;
+
         let response = self
             .client
             .put(format!(
@@ -138,17 +179,22 @@ impl VectorIndex for QdrantStore {
             )));
         }
 
+        let mut body = json!({
+            "vector": query_vector,
+            "limit": query.top_k,
+            "with_payload": true,
+        });
+        if let Some(filter) = build_filter(query) {
+            body["filter"] = filter;
+        }
+
         let response = self
             .client
             .post(format!(
                 "{}/collections/{}/points/search",
                 self.endpoint, self.collection
             ))
-            .json(&json!({
-                "vector": query_vector,
-                "limit": query.top_k,
-                "with_payload": true,
-            }))
+            .json(&body)
             .send()
             .await?;
 
@@ -169,9 +215,10 @@ impl VectorIndex for QdrantStore {
         let mut result = Vec::new();
         for hit in hits {
             let id = hit
-                .pointer("/id")
-                .and_then(Value::as_u64)
-                .map(|id| id.to_string())
+                .pointer("/payload/chunk_id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| hit.pointer("/id").map(|id| id.to_string()))
                 .unwrap_or_default();
             let source_path = hit
                 .pointer("/payload/source_path")
@@ -189,6 +236,10 @@ impl VectorIndex for QdrantStore {
                 .and_then(Value::as_str)
                 .unwrap_or_default()
                 .to_string();
+            let ocr_confidence = hit
+                .pointer("/payload/ocr_confidence")
+                .and_then(Value::as_f64)
+                .map(|confidence| confidence as f32);
 
             result.push(SearchCandidate {
                 chunk_id: id,
@@ -199,9 +250,137 @@ impl VectorIndex for QdrantStore {
                 chunk: None,
                 text: Some(text),
                 mode: SearchMode::Vector,
+                ocr_confidence,
+                citation: hit.get("payload").and_then(citation_from_payload),
+                clause_id: hit
+                    .pointer("/payload/clause_id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                collapsed: 0,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                highlights: Vec::new(),
+                explanation: None,
             });
         }
 
         Ok(result)
     }
+
+    async fn delete_vector_documents(&self, document_ids: &[String]) -> Result<(), SearchError> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+
+        self.delete_points(json!({
+            "must": [{"key": "document_id", "match": {"any": document_ids}}]
+        }))
+        .await
+    }
+}
+
+/// Points written before ids were derived from chunk ids used the chunk
+/// index as id and carry no `chunk_id`; they are dropped when their document
+/// is indexed again so they don't linger as duplicates.
+fn legacy_points_filter(document_ids: &[String]) -> Value {
+    json!({
+        "must": [
+            {"key": "document_id", "match": {"any": document_ids}},
+            {"is_empty": {"key": "chunk_id"}}
+        ]
+    })
+}
+
+/// Qdrant point ids must be integers or UUIDs, so chunk ids are hashed into a
+/// stable UUID; re-ingesting a chunk overwrites its point instead of adding one.
+fn point_id(chunk_id: &str) -> String {
+    let digest = Sha256::digest(chunk_id.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_random_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+/// A chunk's clause id and all of its parents, so `clause:5.2.*` can match
+/// `5.2.1` with a keyword condition.
+fn clause_prefixes(clause_id: Option<&str>) -> Vec<String> {
+    let Some(clause_id) = clause_id else {
+        return Vec::new();
+    };
+    clause_id
+        .match_indices('.')
+        .map(|(end, _)| clause_id[..end].to_string())
+        .chain(std::iter::once(clause_id.to_string()))
+        .collect()
+}
+
+fn build_filter(query: &SearchQuery) -> Option<Value> {
+    let filters = &query.filters;
+    let mut must = Vec::new();
+    for (key, value) in [
+        ("standard", &filters.standard),
+        ("version", &filters.version),
+        ("section_path", &filters.section_path),
+    ] {
+        if let Some(value) = value {
+            must.push(json!({"key": key, "match": {"value": value}}));
+        }
+    }
+    if let Some(clause) = &filters.clause_id {
+        must.push(match clause.strip_suffix(".*") {
+            Some(parent) => json!({"key": "clause_prefixes", "match": {"value": parent}}),
+            None => json!({"key": "clause_id", "match": {"value": clause}}),
+        });
+    }
+
+    let mut filter = json!({});
+    if !must.is_empty() {
+        filter["must"] = json!(must);
+    }
+    if let Some(min_confidence) = query.min_ocr_confidence {
+        filter["should"] = json!([
+            {"key": "ocr_confidence", "range": {"gte": min_confidence}},
+            {"is_null": {"key": "ocr_confidence"}}
+        ]);
+    }
+    (filter != json!({})).then_some(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_filter, clause_prefixes, legacy_points_filter};
+    use crate::SearchQuery;
+    use serde_json::json;
+
+    #[test]
+    fn query_language_filters_become_qdrant_conditions() {
+        assert_eq!(clause_prefixes(Some("5.2.1")), vec!["5", "5.2", "5.2.1"]);
+
+        let query = SearchQuery::parse(r#"relief valve standard:"ISO 4413" clause:5.2.*"#)
+            .expect("valid query");
+        assert_eq!(
+            build_filter(&query),
+            Some(json!({
+                "must": [
+                    {"key": "standard", "match": {"value": "ISO 4413"}},
+                    {"key": "clause_prefixes", "match": {"value": "5.2"}}
+                ]
+            }))
+        );
+        assert_eq!(build_filter(&SearchQuery::default()), None);
+    }
+
+    #[test]
+    fn legacy_points_are_matched_by_document_and_missing_chunk_id() {
+        assert_eq!(
+            legacy_points_filter(&["doc-1".to_string()]),
+            json!({
+                "must": [
+                    {"key": "document_id", "match": {"any": ["doc-1"]}},
+                    {"is_empty": {"key": "chunk_id"}}
+                ]
+            })
+        );
+    }
 }
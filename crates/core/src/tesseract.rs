@@ -0,0 +1,239 @@
+use crate::error::IngestError;
+use crate::extractor::{ExtractionMethod, PageText, PdfExtractor, ScratchDir};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+#[derive(Debug, Clone)]
+pub struct TesseractConfig {
+    pub tesseract_bin: PathBuf,
+    pub pdftoppm_bin: PathBuf,
+    pub languages: Vec<String>,
+    pub dpi: u32,
+}
+
+impl Default for TesseractConfig {
+    fn default() -> Self {
+        Self {
+            tesseract_bin: PathBuf::from("tesseract"),
+            pdftoppm_bin: PathBuf::from("pdftoppm"),
+            languages: vec!["eng".to_string()],
+            dpi: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OcrExtractor {
+    config: TesseractConfig,
+}
+
+impl OcrExtractor {
+    pub fn new(config: TesseractConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &TesseractConfig {
+        &self.config
+    }
+
+    fn rasterize_all(
+        &self,
+        path: &Path,
+        workdir: &Path,
+    ) -> Result<Vec<(u32, PathBuf)>, IngestError> {
+        let mut command = Command::new(&self.config.pdftoppm_bin);
+        command
+            .arg("-r")
+            .arg(self.config.dpi.to_string())
+            .arg("-png")
+            .arg(path)
+            .arg(workdir.join("page"));
+        run_tool(&mut command, &self.config.pdftoppm_bin, path)?;
+
+        let mut images = fs::read_dir(workdir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|image| rendered_page_number(&image).map(|number| (number, image)))
+            .collect::<Vec<_>>();
+        images.sort_unstable();
+        Ok(images)
+    }
+
+    fn recognize(&self, image: &Path, page_number: u32) -> Result<PageText, IngestError> {
+        let mut command = Command::new(&self.config.tesseract_bin);
+        command.arg(image).arg("stdout");
+        if !self.config.languages.is_empty() {
+            command.arg("-l").arg(self.config.languages.join("+"));
+        }
+        command.arg("tsv");
+
+        let output = run_tool(&mut command, &self.config.tesseract_bin, image)?;
+        let (text, confidence) = parse_tesseract_tsv(&String::from_utf8_lossy(&output.stdout));
+
+        Ok(PageText {
+            number: page_number,
+            text,
+            method: ExtractionMethod::Ocr,
+            confidence,
+            section: None,
+            spans: Vec::new(),
+            failure: None,
+        })
+    }
+}
+
+impl PdfExtractor for OcrExtractor {
+    fn name(&self) -> &str {
+        "tesseract"
+    }
+
+    fn cache_identity(&self) -> Option<String> {
+        Some(format!(
+            "tesseract:{}:{}dpi",
+            self.config.languages.join("+"),
+            self.config.dpi
+        ))
+    }
+
+    fn extract_pages(&self, path: &Path) -> Result<Vec<PageText>, IngestError> {
+        let workdir = ScratchDir::create()?;
+        let images = self.rasterize_all(path, workdir.path())?;
+
+        let pages = images
+            .iter()
+            .map(|(page_number, image)| self.recognize(image, *page_number))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if pages.iter().all(|page| page.text.trim().is_empty()) {
+            return Err(IngestError::OcrFailed(format!(
+                "tesseract found no text in {}",
+                path.display()
+            )));
+        }
+
+        Ok(pages)
+    }
+}
+
+fn run_tool(command: &mut Command, program: &Path, input: &Path) -> Result<Output, IngestError> {
+    let output = command.output().map_err(|error| match error.kind() {
+        ErrorKind::NotFound => IngestError::OcrUnavailable(format!(
+            "{} is not installed or not on PATH",
+            program.display()
+        )),
+        _ => IngestError::Io(error),
+    })?;
+
+    if !output.status.success() {
+        return Err(IngestError::OcrFailed(format!(
+            "{} failed on {} ({}): {}",
+            program.display(),
+            input.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output)
+}
+
+fn rendered_page_number(image: &Path) -> Option<u32> {
+    if image.extension().and_then(|ext| ext.to_str()) != Some("png") {
+        return None;
+    }
+    image
+        .file_stem()?
+        .to_str()?
+        .strip_prefix("page-")?
+        .parse()
+        .ok()
+}
+
+fn parse_tesseract_tsv(tsv: &str) -> (String, Option<f32>) {
+    let mut lines = BTreeMap::<(u32, u32, u32, u32), Vec<&str>>::new();
+    let mut confidence_sum = 0.0f32;
+    let mut word_count = 0usize;
+
+    for row in tsv.lines().skip(1) {
+        let columns = row.split('\t').collect::<Vec<_>>();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+
+        let word = columns[11].trim();
+        let confidence = columns[10].parse::<f32>().unwrap_or(-1.0);
+        if word.is_empty() || confidence < 0.0 {
+            continue;
+        }
+
+        let key = |index: usize| columns[index].parse::<u32>().unwrap_or_default();
+        lines
+            .entry((key(1), key(2), key(3), key(4)))
+            .or_default()
+            .push(word);
+        confidence_sum += confidence;
+        word_count += 1;
+    }
+
+    let text = lines
+        .values()
+        .map(|words| words.join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let confidence = (word_count > 0).then(|| confidence_sum / word_count as f32 / 100.0);
+
+    (text, confidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tesseract_tsv, rendered_page_number, OcrExtractor, TesseractConfig};
+    use crate::{FailureCategory, PdfExtractor};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn tsv_words_are_grouped_by_line_with_mean_confidence() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t100\t100\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t0\t0\t10\t10\t90\tRelief\n\
+                   5\t1\t1\t1\t1\t2\t0\t0\t10\t10\t80\tvalve\n\
+                   5\t1\t1\t1\t2\t1\t0\t0\t10\t10\t70\t210\n\
+                   5\t1\t1\t1\t2\t2\t0\t0\t10\t10\t-1\t \n\
+                   5\t1\t1\t1\t2\t3\t0\t0\t10\t10\t60\tbar\n";
+
+        let (text, confidence) = parse_tesseract_tsv(tsv);
+
+        assert_eq!(text, "Relief valve\n210 bar");
+        assert_eq!(confidence, Some(0.75));
+    }
+
+    #[test]
+    fn rendered_page_numbers_ignore_zero_padding() {
+        assert_eq!(
+            rendered_page_number(Path::new("/tmp/x/page-007.png")),
+            Some(7)
+        );
+        assert_eq!(
+            rendered_page_number(Path::new("/tmp/x/page-12.png")),
+            Some(12)
+        );
+        assert_eq!(rendered_page_number(Path::new("/tmp/x/other.png")), None);
+    }
+
+    #[test]
+    fn missing_binaries_are_reported_as_ocr_unavailable() {
+        let extractor = OcrExtractor::new(TesseractConfig {
+            pdftoppm_bin: PathBuf::from("docneedle-missing-pdftoppm"),
+            ..TesseractConfig::default()
+        });
+
+        let error = extractor
+            .extract_pages(Path::new("manual.pdf"))
+            .map(|_| ())
+            .expect_err("missing binary should fail");
+
+        assert_eq!(error.category(), FailureCategory::OcrUnavailable);
+    }
+}
@@ -0,0 +1,151 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream, StringFormat};
+use md5::{Digest, Md5};
+use std::path::Path;
+
+const PAD_BYTES: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08,
+    0x2E, 0x2E, 0x00, 0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+pub(crate) fn build_pdf(pages: &[&str]) -> Document {
+    let positioned = pages
+        .iter()
+        .map(|text| {
+            text.lines()
+                .enumerate()
+                .map(|(line_no, line)| (72, 720 - 14 * line_no as i64, line))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    build_positioned_pdf(&positioned.iter().map(Vec::as_slice).collect::<Vec<_>>())
+}
+
+/// Pages of `(x, y, text)` lines drawn in 12 pt Courier.
+pub(crate) fn build_positioned_pdf(pages: &[&[(i64, i64, &str)]]) -> Document {
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = document.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let mut kids = Vec::new();
+    for lines in pages {
+        let mut operations = Vec::new();
+        for &(x, y, line) in lines.iter() {
+            operations.extend([
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![x.into(), y.into()]),
+                Operation::new("Tj", vec![Object::string_literal(line)]),
+                Operation::new("ET", vec![]),
+            ]);
+        }
+        let content = Content { operations };
+        let content_id = document.add_object(Stream::new(
+            dictionary! {},
+            content.encode().expect("test content should encode"),
+        ));
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(page_id.into());
+    }
+
+    document.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        }),
+    );
+    let catalog_id = document.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    document.trailer.set("Root", catalog_id);
+    document
+}
+
+pub(crate) fn write_pdf(path: &Path, pages: &[&str]) {
+    build_pdf(pages)
+        .save(path)
+        .expect("test pdf should be written");
+}
+
+/// Applies standard security handler revision 2 (40-bit RC4) with `user_password`.
+pub(crate) fn encrypt_rc4(document: &mut Document, user_password: &str) {
+    let owner_hash = vec![0x42u8; 32];
+    let file_id = b"docneedle-test-id".to_vec();
+    let permissions: i32 = -4;
+
+    let password = user_password.as_bytes();
+    let mut seed = password[..password.len().min(32)].to_vec();
+    seed.extend_from_slice(&PAD_BYTES[..32 - seed.len()]);
+    seed.extend_from_slice(&owner_hash);
+    seed.extend_from_slice(&permissions.to_le_bytes());
+    seed.extend_from_slice(&file_id);
+    let key = Md5::digest(&seed)[..5].to_vec();
+
+    for (&(number, generation), object) in document.objects.iter_mut() {
+        let mut object_seed = key.clone();
+        object_seed.extend_from_slice(&number.to_le_bytes()[..3]);
+        object_seed.extend_from_slice(&generation.to_le_bytes()[..2]);
+        let object_key = Md5::digest(&object_seed)[..10].to_vec();
+        match object {
+            Object::Stream(stream) => {
+                let encrypted = rc4(&object_key, &stream.content);
+                stream.set_content(encrypted);
+            }
+            Object::String(content, _) => *content = rc4(&object_key, content),
+            _ => {}
+        }
+    }
+
+    let encrypt_id = document.add_object(dictionary! {
+        "Filter" => "Standard",
+        "V" => 1,
+        "R" => 2,
+        "Length" => 40,
+        "P" => permissions as i64,
+        "O" => Object::String(owner_hash, StringFormat::Hexadecimal),
+        "U" => Object::String(rc4(&key, &PAD_BYTES), StringFormat::Hexadecimal),
+    });
+    document.trailer.set("Encrypt", encrypt_id);
+    document.trailer.set(
+        "ID",
+        vec![
+            Object::String(file_id.clone(), StringFormat::Hexadecimal),
+            Object::String(file_id, StringFormat::Hexadecimal),
+        ],
+    );
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0usize;
+    for i in 0..256 {
+        j = (j + state[i] as usize + key[i % key.len()] as usize) % 256;
+        state.swap(i, j);
+    }
+
+    let (mut i, mut j) = (0usize, 0usize);
+    data.iter()
+        .map(|byte| {
+            i = (i + 1) % 256;
+            j = (j + state[i] as usize) % 256;
+            state.swap(i, j);
+            byte ^ state[(state[i] as usize + state[j] as usize) % 256]
+        })
+        .collect()
+}
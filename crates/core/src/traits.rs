@@ -1,5 +1,6 @@
-use crate::{SearchCandidate, SearchError, SearchQuery};
+use crate::{ChunkContext, SearchCandidate, SearchError, SearchQuery};
 use async_trait::async_trait;
+use std::collections::HashMap;
 
 #[async_trait]
 pub trait KeywordIndex {
@@ -9,6 +10,29 @@ pub trait KeywordIndex {
         &self,
         query: &SearchQuery,
     ) -> Result<Vec<SearchCandidate>, SearchError>;
+
+    async fn delete_keyword_documents(&self, _document_ids: &[String]) -> Result<(), SearchError>
+    where
+        Self: Sync,
+    {
+        Err(SearchError::Unsupported(
+            "deleting keyword documents".to_string(),
+        ))
+    }
+
+    /// Up to `window` chunks on either side of each of `chunk_ids` within its
+    /// document, keyed by chunk id. Stores that cannot look them up return no
+    /// context.
+    async fn neighbouring_chunks(
+        &self,
+        _chunk_ids: &[String],
+        _window: usize,
+    ) -> Result<HashMap<String, ChunkContext>, SearchError>
+    where
+        Self: Sync,
+    {
+        Ok(HashMap::new())
+    }
 }
 
 #[async_trait]
@@ -24,6 +48,15 @@ pub trait VectorIndex {
         query_vector: &[f32],
         query: &SearchQuery,
     ) -> Result<Vec<SearchCandidate>, SearchError>;
+
+    async fn delete_vector_documents(&self, _document_ids: &[String]) -> Result<(), SearchError>
+    where
+        Self: Sync,
+    {
+        Err(SearchError::Unsupported(
+            "deleting vector documents".to_string(),
+        ))
+    }
 }
 
 #[async_trait]
@@ -33,5 +66,75 @@ pub trait GraphIndex {
     async fn related_chunks(
         &self,
         chunk_ids: &[String],
+        query: &SearchQuery,
     ) -> Result<Vec<SearchCandidate>, SearchError>;
+
+    async fn delete_graph_documents(&self, _document_ids: &[String]) -> Result<(), SearchError>
+    where
+        Self: Sync,
+    {
+        Err(SearchError::Unsupported(
+            "deleting graph documents".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeywordIndex, VectorIndex};
+    use crate::{PdfChunk, SearchCandidate, SearchError, SearchQuery};
+    use async_trait::async_trait;
+
+    struct AppendOnlyIndex;
+
+    #[async_trait]
+    impl KeywordIndex for AppendOnlyIndex {
+        async fn index_keyword_chunks(&self, _chunks: &[PdfChunk]) -> Result<(), SearchError> {
+            Ok(())
+        }
+
+        async fn search_keyword(
+            &self,
+            _query: &SearchQuery,
+        ) -> Result<Vec<SearchCandidate>, SearchError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[async_trait]
+    impl VectorIndex for AppendOnlyIndex {
+        async fn index_vector_chunks(
+            &self,
+            _chunks: &[PdfChunk],
+            _embeddings: &[Vec<f32>],
+        ) -> Result<(), SearchError> {
+            Ok(())
+        }
+
+        async fn search_vector(
+            &self,
+            _query_vector: &[f32],
+            _query: &SearchQuery,
+        ) -> Result<Vec<SearchCandidate>, SearchError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_without_deletion_report_it_as_unsupported() {
+        let error = AppendOnlyIndex
+            .delete_vector_documents(&["doc-1".to_string()])
+            .await
+            .expect_err("deletion is not implemented");
+        assert!(matches!(error, SearchError::Unsupported(_)));
+    }
+
+    #[tokio::test]
+    async fn stores_without_neighbour_lookup_return_no_context() {
+        let context = AppendOnlyIndex
+            .neighbouring_chunks(&["chunk-1".to_string()], 2)
+            .await
+            .expect("no context instead of an error");
+        assert!(context.is_empty());
+    }
 }
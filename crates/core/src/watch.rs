@@ -0,0 +1,439 @@
+use crate::archives::VIRTUAL_SEPARATOR;
+use crate::discovery::DiscoveredFiles;
+use crate::ingest::{digest_file, discover_documents, generate_document_id};
+use crate::{FileStatus, IngestError, IngestionOptions, IngestionReport, PdfChunk, SearchError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEventKind, Debouncer};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub checksum: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Documents indexed from this file; several for archives.
+    pub document_ids: Vec<String>,
+}
+
+/// Files a watched folder has already been indexed from, persisted between
+/// runs so restarts only re-ingest what changed while the watcher was down.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexedFiles {
+    files: BTreeMap<PathBuf, IndexedFile>,
+}
+
+/// The stores a watched folder is kept in sync with.
+#[async_trait]
+pub trait WatchTarget {
+    async fn delete_documents(&self, document_ids: &[String]) -> Result<(), SearchError>;
+
+    async fn index_chunks(&self, chunks: &[PdfChunk]) -> Result<(), SearchError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    /// Added or modified files, ready for `ingest_discovered`.
+    pub changed: DiscoveredFiles,
+    pub removed: Vec<PathBuf>,
+    checksums: BTreeMap<PathBuf, String>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.changed.files.is_empty()
+            && self.changed.oversized.is_empty()
+            && self.removed.is_empty()
+    }
+}
+
+impl IndexedFiles {
+    pub fn load(path: &Path) -> Result<Self, IngestError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), IngestError> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let staging = path.with_extension("json.tmp");
+        fs::write(&staging, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&staging, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&IndexedFile> {
+        self.files.get(path)
+    }
+
+    /// Compares the folder with what was indexed. Only files whose size or
+    /// modification time moved are re-hashed, and `busy` paths (still being
+    /// written) are left for a later scan.
+    pub fn scan(
+        &self,
+        folder: &Path,
+        options: &IngestionOptions,
+        busy: &HashSet<PathBuf>,
+    ) -> Result<ChangeSet, IngestError> {
+        let discovered = discover_documents(folder, options)?;
+        let mut changes = ChangeSet::default();
+        let mut seen = HashSet::new();
+
+        for path in discovered.files {
+            seen.insert(path.clone());
+            if busy.contains(&path) {
+                continue;
+            }
+            // Files that vanish or cannot be read mid-scan are picked up by the next event.
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let known = self.files.get(&path);
+            if known.is_some_and(|known| {
+                known.size == metadata.len() && known.modified == modified(&metadata)
+            }) {
+                continue;
+            }
+
+            let Ok(checksum) = digest_file(&path) else {
+                continue;
+            };
+            if known.is_some_and(|known| known.checksum == checksum) {
+                continue;
+            }
+            changes.checksums.insert(path.clone(), checksum);
+            changes.changed.files.push(path);
+        }
+
+        for (path, size) in discovered.oversized {
+            seen.insert(path.clone());
+            if !busy.contains(&path)
+                && self
+                    .files
+                    .get(&path)
+                    .map_or(true, |known| known.size != size)
+            {
+                changes.changed.oversized.push((path, size));
+            }
+        }
+
+        changes.removed = self
+            .files
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .filter(|path| {
+                !path.exists()
+                    || !discovered
+                        .unreadable
+                        .iter()
+                        .any(|unreadable| path.starts_with(&unreadable.path))
+            })
+            .cloned()
+            .collect();
+        changes.changed.unreadable = discovered.unreadable;
+        Ok(changes)
+    }
+
+    /// Document ids that must be removed from the stores before `changes` is
+    /// indexed: everything from removed files and the old version of changed
+    /// ones, including files indexed by a plain `ingest` before watching began.
+    pub fn stale_document_ids(&self, changes: &ChangeSet) -> Vec<String> {
+        let mut document_ids = BTreeSet::new();
+        for path in changes
+            .removed
+            .iter()
+            .chain(&changes.changed.files)
+            .chain(changes.changed.oversized.iter().map(|(path, _)| path))
+        {
+            document_ids.insert(generate_document_id(path));
+            if let Some(file) = self.files.get(path) {
+                document_ids.extend(file.document_ids.iter().cloned());
+            }
+        }
+        document_ids.into_iter().collect()
+    }
+
+    /// Records the outcome of ingesting `changes`. Files that failed are still
+    /// remembered by checksum so they are only retried once they change again.
+    pub fn apply(&mut self, changes: &ChangeSet, report: &IngestionReport) {
+        for path in &changes.removed {
+            self.files.remove(path);
+        }
+
+        let mut document_ids = BTreeMap::<PathBuf, Vec<String>>::new();
+        for file in &report.files {
+            if file.status != FileStatus::Ingested {
+                continue;
+            }
+            let reported = file.path.to_string_lossy();
+            let container = reported
+                .split_once(VIRTUAL_SEPARATOR)
+                .map_or(reported.as_ref(), |(container, _)| container);
+            document_ids
+                .entry(PathBuf::from(container))
+                .or_default()
+                .push(generate_document_id(&file.path));
+        }
+
+        let paths = changes
+            .changed
+            .files
+            .iter()
+            .chain(changes.changed.oversized.iter().map(|(path, _)| path));
+        for path in paths {
+            let Ok(metadata) = fs::metadata(path) else {
+                self.files.remove(path);
+                continue;
+            };
+            self.files.insert(
+                path.clone(),
+                IndexedFile {
+                    checksum: changes.checksums.get(path).cloned().unwrap_or_default(),
+                    size: metadata.len(),
+                    modified: modified(&metadata),
+                    document_ids: document_ids.remove(path).unwrap_or_default(),
+                },
+            );
+        }
+    }
+
+    /// Replaces the stale documents of `changes` in `target` with the chunks of
+    /// `report`, then records the change. Nothing is recorded when the target
+    /// fails, so the next scan reports the same changes and retries them.
+    pub async fn sync<T: WatchTarget + Sync + ?Sized>(
+        &mut self,
+        changes: &ChangeSet,
+        report: &IngestionReport,
+        target: &T,
+    ) -> Result<(), SearchError> {
+        target
+            .delete_documents(&self.stale_document_ids(changes))
+            .await?;
+        target.index_chunks(&report.chunks).await?;
+        self.apply(changes, report);
+        Ok(())
+    }
+}
+
+fn modified(metadata: &fs::Metadata) -> Option<DateTime<Utc>> {
+    metadata.modified().ok().map(DateTime::<Utc>::from)
+}
+
+/// Paths reported by one debounced batch of filesystem events.
+#[derive(Debug, Clone, Default)]
+pub struct WatchBatch {
+    pub settled: Vec<PathBuf>,
+    /// Paths that were still being written when the batch was emitted.
+    pub busy: HashSet<PathBuf>,
+}
+
+/// Recursive inotify (or platform equivalent) watch on a folder whose events
+/// are debounced so that files still being copied are not ingested half-written.
+pub struct FolderWatcher {
+    _debouncer: Debouncer<notify::RecommendedWatcher>,
+    events: Receiver<DebounceEventResult>,
+}
+
+impl FolderWatcher {
+    pub fn new(folder: &Path, debounce: Duration) -> Result<Self, IngestError> {
+        let (sender, events) = mpsc::channel();
+        let mut debouncer = new_debouncer(debounce, sender).map_err(watch_error)?;
+        debouncer
+            .watcher()
+            .watch(folder, RecursiveMode::Recursive)
+            .map_err(watch_error)?;
+
+        Ok(Self {
+            _debouncer: debouncer,
+            events,
+        })
+    }
+
+    /// Blocks until the next batch of events; `None` once the watcher stops.
+    pub fn next_batch(&self) -> Option<Result<WatchBatch, IngestError>> {
+        let events = match self.events.recv().ok()? {
+            Ok(events) => events,
+            Err(error) => return Some(Err(watch_error(error))),
+        };
+
+        let mut batch = WatchBatch::default();
+        for event in events {
+            match event.kind {
+                DebouncedEventKind::Any => batch.settled.push(event.path),
+                DebouncedEventKind::AnyContinuous => {
+                    batch.busy.insert(event.path);
+                }
+                _ => {}
+            }
+        }
+        Some(Ok(batch))
+    }
+}
+
+fn watch_error(error: notify::Error) -> IngestError {
+    IngestError::Io(std::io::Error::other(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexedFiles, WatchTarget};
+    use crate::{ingest_discovered, IngestionOptions, PdfChunk, SearchError, SourceRegistry};
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[test]
+    fn scans_report_added_changed_and_removed_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let notes = dir.path().join("notes.txt");
+        let procedure = dir.path().join("procedure.md");
+        fs::write(&notes, "Relief valve set to 210 bar.")?;
+        fs::write(&procedure, "# Pump\n\nOpen the suction valve first.")?;
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+        let no_busy = HashSet::new();
+
+        let mut indexed = IndexedFiles::default();
+        let changes = indexed.scan(dir.path(), &options, &no_busy)?;
+        assert_eq!(changes.changed.files.len(), 2);
+        let report = ingest_discovered(dir.path(), changes.changed.clone(), options.clone());
+        indexed.apply(&changes, &report);
+        assert_eq!(indexed.len(), 2);
+        assert!(indexed.scan(dir.path(), &options, &no_busy)?.is_empty());
+
+        fs::write(&notes, "Relief valve set to 240 bar.")?;
+        fs::remove_file(&procedure)?;
+        let changes = indexed.scan(dir.path(), &options, &no_busy)?;
+        assert_eq!(changes.changed.files, vec![notes.clone()]);
+        assert_eq!(changes.removed, vec![procedure.clone()]);
+
+        let stale = indexed.stale_document_ids(&changes);
+        assert_eq!(stale.len(), 2);
+        assert!(stale.contains(&crate::generate_document_id(&procedure)));
+
+        let busy = HashSet::from([notes.clone()]);
+        assert!(indexed
+            .scan(dir.path(), &options, &busy)?
+            .changed
+            .files
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn state_round_trips_through_json() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("notes.txt"), "Relief valve set to 210 bar.")?;
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+        let mut indexed = IndexedFiles::default();
+        let changes = indexed.scan(dir.path(), &options, &HashSet::new())?;
+        let report = ingest_discovered(dir.path(), changes.changed.clone(), options.clone());
+        indexed.apply(&changes, &report);
+
+        let state = dir.path().join("state").join("watch.json");
+        indexed.save(&state)?;
+        let restored = IndexedFiles::load(&state)?;
+
+        assert_eq!(
+            restored.get(&dir.path().join("notes.txt")),
+            indexed.get(&dir.path().join("notes.txt"))
+        );
+        assert!(restored
+            .scan(dir.path(), &options, &HashSet::new())?
+            .is_empty());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct FlakyStore {
+        down: AtomicBool,
+        documents: Mutex<HashSet<String>>,
+    }
+
+    #[async_trait]
+    impl WatchTarget for FlakyStore {
+        async fn delete_documents(&self, document_ids: &[String]) -> Result<(), SearchError> {
+            let mut documents = self.documents.lock().expect("documents lock");
+            for document_id in document_ids {
+                documents.remove(document_id);
+            }
+            Ok(())
+        }
+
+        async fn index_chunks(&self, chunks: &[PdfChunk]) -> Result<(), SearchError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(SearchError::Request("store unavailable".to_string()));
+            }
+            let mut documents = self.documents.lock().expect("documents lock");
+            documents.extend(chunks.iter().map(|chunk| chunk.document_id.clone()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_syncs_are_not_recorded_and_retried_by_the_next_scan(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "Relief valve set to 210 bar.")?;
+        let options = IngestionOptions {
+            min_chunk_chars: 10,
+            sources: SourceRegistry::all(),
+            ..IngestionOptions::default()
+        };
+        let no_busy = HashSet::new();
+        let store = FlakyStore::default();
+        let mut indexed = IndexedFiles::default();
+
+        let changes = indexed.scan(dir.path(), &options, &no_busy)?;
+        let report = ingest_discovered(dir.path(), changes.changed.clone(), options.clone());
+        indexed.sync(&changes, &report, &store).await?;
+        assert_eq!(store.documents.lock().expect("documents lock").len(), 1);
+
+        fs::write(&notes, "Relief valve set to 240 bar.")?;
+        store.down.store(true, Ordering::SeqCst);
+        let changes = indexed.scan(dir.path(), &options, &no_busy)?;
+        let report = ingest_discovered(dir.path(), changes.changed.clone(), options.clone());
+        assert!(indexed.sync(&changes, &report, &store).await.is_err());
+        assert!(store.documents.lock().expect("documents lock").is_empty());
+
+        store.down.store(false, Ordering::SeqCst);
+        let retried = indexed.scan(dir.path(), &options, &no_busy)?;
+        assert_eq!(retried.changed.files, vec![notes.clone()]);
+        let report = ingest_discovered(dir.path(), retried.changed.clone(), options.clone());
+        indexed.sync(&retried, &report, &store).await?;
+        assert_eq!(store.documents.lock().expect("documents lock").len(), 1);
+        assert!(indexed.scan(dir.path(), &options, &no_busy)?.is_empty());
+        Ok(())
+    }
+}